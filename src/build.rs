@@ -0,0 +1,188 @@
+//! Construction of trees from a stream of enter/leave events.
+//!
+//! This is the inverse of traversing a tree with a cursor: instead of a stack
+//! of nodes that already exist, [`TreeBuilder`] keeps a stack of nodes that
+//! are still being built. Each [`Event::Enter`] starts a new node as a child
+//! of the innermost open node, and each [`Event::Leave`] finishes the
+//! innermost open node and appends it to its parent.
+//!
+//! ```
+//! use tree_cursor::build::{self, BuildNode, Event};
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl BuildNode for Node {
+//!     type Value = &'static str;
+//!
+//!     fn create(value: &'static str) -> Self {
+//!         Node(value, vec![])
+//!     }
+//!
+//!     fn append_child(&mut self, child: Self) {
+//!         self.1.push(child);
+//!     }
+//! }
+//!
+//! let t: Node = build::build(vec![
+//!     Event::Enter("foo"),
+//!     Event::Enter("bar"),
+//!     Event::Leave,
+//!     Event::Leave,
+//! ]).unwrap();
+//! assert_eq!(t, Node("foo", vec![Node("bar", vec![])]));
+//! ```
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+/// A node type that [`TreeBuilder`] can construct.
+pub trait BuildNode: Sized {
+    /// The value carried by an [`Event::Enter`] event.
+    type Value;
+
+    /// Creates a node with no children.
+    fn create(value: Self::Value) -> Self;
+
+    /// Appends `child` after this node's existing children.
+    fn append_child(&mut self, child: Self);
+}
+
+/// An event consumed by [`TreeBuilder`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Event<V> {
+    /// Starts a new node.
+    Enter(V),
+    /// Finishes the most recently started node that isn't finished yet.
+    Leave,
+}
+
+/// An error reported by [`TreeBuilder`]. Positions count events from 0.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BuildError {
+    /// The [`Event::Leave`] at this position had no node to finish.
+    UnmatchedLeave(usize),
+    /// The [`Event::Enter`] at this position came after the root was
+    /// finished. A tree has exactly one root.
+    ExtraRoot(usize),
+    /// The events ended at this position with `depth` nodes still unfinished.
+    Unfinished { pos: usize, depth: usize },
+    /// There were no events, so there's no root.
+    Empty,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::UnmatchedLeave(pos) => {
+                write!(f, "unmatched Leave event at position {}", pos)
+            },
+            BuildError::ExtraRoot(pos) => {
+                write!(f, "Enter event after root at position {}", pos)
+            },
+            BuildError::Unfinished { pos, depth } => write!(
+                f,
+                "events ended at position {} with {} unfinished nodes",
+                pos,
+                depth,
+            ),
+            BuildError::Empty => write!(f, "no events"),
+        }
+    }
+}
+
+impl Error for BuildError { }
+
+/// Builds a tree from [`Event`]s. See the [module documentation](self).
+#[derive(Debug)]
+pub struct TreeBuilder<N> {
+    stack: Vec<N>,
+    root: Option<N>,
+    pos: usize,
+}
+
+impl<N: BuildNode> Default for TreeBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: BuildNode> TreeBuilder<N> {
+    /// Creates a new `TreeBuilder` that hasn't consumed any events.
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            root: None,
+            pos: 0,
+        }
+    }
+
+    /// Returns the number of unfinished nodes.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the number of events consumed so far, including rejected
+    /// ones.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Consumes one event. If the event is rejected, the builder's state is
+    /// unchanged, apart from its position.
+    pub fn push(&mut self, event: Event<N::Value>) -> Result<(), BuildError> {
+        match event {
+            Event::Enter(value) => self.enter(value),
+            Event::Leave => self.leave(),
+        }
+    }
+
+    /// Equivalent to `self.push(Event::Enter(value))`.
+    pub fn enter(&mut self, value: N::Value) -> Result<(), BuildError> {
+        let pos = self.pos;
+        self.pos += 1;
+        if self.root.is_some() {
+            return Err(BuildError::ExtraRoot(pos));
+        }
+        self.stack.push(N::create(value));
+        Ok(())
+    }
+
+    /// Equivalent to `self.push(Event::Leave)`.
+    pub fn leave(&mut self) -> Result<(), BuildError> {
+        let pos = self.pos;
+        self.pos += 1;
+        let node = self.stack.pop().ok_or(BuildError::UnmatchedLeave(pos))?;
+        match self.stack.last_mut() {
+            Some(parent) => parent.append_child(node),
+            None => self.root = Some(node),
+        }
+        Ok(())
+    }
+
+    /// Returns the finished tree.
+    pub fn finish(self) -> Result<N, BuildError> {
+        if !self.stack.is_empty() {
+            Err(BuildError::Unfinished {
+                pos: self.pos,
+                depth: self.stack.len(),
+            })
+        } else {
+            self.root.ok_or(BuildError::Empty)
+        }
+    }
+}
+
+/// Builds a tree from `events`, stopping at the first error.
+pub fn build<N, I>(events: I) -> Result<N, BuildError>
+where
+    N: BuildNode,
+    I: IntoIterator<Item = Event<N::Value>>,
+{
+    let mut b = TreeBuilder::new();
+    for event in events {
+        b.push(event)?;
+    }
+    b.finish()
+}
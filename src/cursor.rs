@@ -1,42 +1,203 @@
+use alloc::vec::Vec;
 use prelude::*;
-use std::marker::PhantomData;
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+#[cfg(feature = "serde")]
+use core::fmt;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use observe::{Move, NoObserver, Observer};
+use stack::{Entry, Stack};
 
 /// A cursor that holds a shared reference to its tree.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct TreeCursor<'n: 'f, 'f, N: 'n> {
+///
+/// `O` is an [`Observer`] that's told about every move the cursor makes. See
+/// the [`observe`](::observe) module.
+#[derive(Clone, Debug)]
+pub struct TreeCursor<'n: 'f, 'f, N: 'n, O = NoObserver> {
     root: PhantomData<&'n N>,
     frozen: PhantomData<&'f ()>,
-    stack: Vec<(*const N, usize)>,
+    stack: Stack<N>,
+    observer: O,
 }
 
 impl<'n, N: 'n> TreeCursor<'n, 'n, N> {
     /// Creates a new `TreeCursor` starting at `root`.
     pub fn new(root: &'n N) -> Self {
+        Self::with_observer(root, NoObserver)
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n> TreeCursor<'n, 'f, N> {
+    fn from_stack(stack: Stack<N>) -> Self {
         Self {
             root: PhantomData,
             frozen: PhantomData,
-            stack: vec![(root as *const N, 0)],
+            stack,
+            observer: NoObserver,
         }
     }
 }
 
-impl<'n: 'f, 'f, N: 'n> TreeCursor<'n, 'f, N> {
-    fn top(&self) -> &(*const N, usize) {
-        self.stack.last().unwrap()
+// Cursors are compared by position and counters alone, whatever their
+// observers have seen.
+impl<'n: 'f, 'f, N: 'n, O> PartialEq for TreeCursor<'n, 'f, N, O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.stack == other.stack
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n, O> Eq for TreeCursor<'n, 'f, N, O> { }
+
+impl<'n: 'f, 'f, N: 'n, O> Hash for TreeCursor<'n, 'f, N, O> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.stack.hash(state)
+    }
+}
+
+impl<'n, N: 'n, O: Observer> TreeCursor<'n, 'n, N, O> {
+    /// Creates a new `TreeCursor` starting at `root`, which reports its moves
+    /// to `observer`.
+    pub fn with_observer(root: &'n N, observer: O) -> Self {
+        Self {
+            root: PhantomData,
+            frozen: PhantomData,
+            stack: Stack::new((NonNull::from(root), 0)),
+            observer,
+        }
+    }
+
+    /// Moves the cursor to the root of another tree (or to a different node
+    /// in the same tree, which then becomes the root), as if `self` had just
+    /// been created by [`new`]. Unlike creating a new cursor, this reuses any
+    /// memory `self` has allocated for deep trees.
+    ///
+    /// This is reported to the observer as moving up to the root and
+    /// resetting its counter.
+    ///
+    /// [`new`]: TreeCursor::new
+    pub fn reset(&mut self, root: &'n N) {
+        let from = self.depth();
+        self.stack.reset((NonNull::from(root), 0));
+        self.moved_up(from);
+        self.observer.on_move(Move::Zero { depth: 0 });
+    }
+}
+
+impl<'n, N: 'n + Up> TreeCursor<'n, 'n, N> {
+    /// Creates a new `TreeCursor` at `node`, whose root is the root of
+    /// `node`'s whole tree rather than `node` itself. The stack is rebuilt by
+    /// following [`Up::parent`] links, and each ancestor's "next child"
+    /// counter is set as if the cursor had moved down to `node` with
+    /// [`down`]: one past [`Up::index_in_parent`].
+    ///
+    /// [`down`]: TreeCursor::down
+    pub fn new_at(node: &'n N) -> Self {
+        let mut path = vec![(NonNull::from(node), 0)];
+        let mut child = node;
+        while let Some(parent) = child.parent() {
+            path.push((NonNull::from(parent), child.index_in_parent() + 1));
+            child = parent;
+        }
+        let mut entries = path.into_iter().rev();
+        let mut stack = Stack::new(entries.next().unwrap());
+        for e in entries {
+            stack.push(e);
+        }
+        TreeCursor::from_stack(stack)
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n, O: Observer> TreeCursor<'n, 'f, N, O> {
+    fn top(&self) -> &Entry<N> {
+        self.stack.last()
+    }
+
+    fn top_mut(&mut self) -> &mut Entry<N> {
+        self.stack.last_mut()
+    }
+
+    // Pushes a node that `down_map_ptr` or `down_ptr` just returned.
+    fn push(&mut self, ptr: NonNull<N>) {
+        let idx = self.top().1 - 1;
+        self.stack.push((ptr, 0));
+        let depth = self.depth();
+        self.observer.on_move(Move::Down { depth, idx });
+    }
+
+    // Reports that a node `down_map_ptr` or `down_ptr` just returned was
+    // handed to a new cursor.
+    fn gave_child(&mut self) {
+        let depth = self.depth();
+        let idx = self.top().1 - 1;
+        self.observer.on_move(Move::DownTake { depth, idx });
+    }
+
+    // Pushes an entry for a child of the active node, reported as moving
+    // down to it with `down` and then setting its counter.
+    fn restore(&mut self, e: Entry<N>) {
+        let idx = self.top().1 - 1;
+        self.set_counter(idx);
+        self.top_mut().1 += 1;
+        self.push(e.0);
+        if e.1 != 0 {
+            self.set_counter(e.1);
+        }
+    }
+
+    fn pop(&mut self) -> Option<Entry<N>> {
+        let top = self.stack.pop()?;
+        let depth = self.depth();
+        self.observer.on_move(Move::Up { depth });
+        Some(top)
+    }
+
+    fn take_top(&mut self) -> Option<Entry<N>> {
+        let top = self.stack.pop()?;
+        let depth = self.depth();
+        self.observer.on_move(Move::Take { depth });
+        Some(top)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        let from = self.depth();
+        self.stack.truncate(len);
+        self.moved_up(from);
+    }
+
+    // Reports a move up from depth `from` to the current depth.
+    fn moved_up(&mut self, from: usize) {
+        for depth in (self.depth()..from).rev() {
+            self.observer.on_move(Move::Up { depth });
+        }
+    }
+
+    pub(crate) fn set_counter(&mut self, idx: usize) {
+        self.top_mut().1 = idx;
+        let depth = self.depth();
+        self.observer.on_move(Move::Set { depth, idx });
     }
 
-    fn top_mut(&mut self) -> &mut (*const N, usize) {
-        self.stack.last_mut().unwrap()
+    /// Returns a shared reference to the observer.
+    pub fn observer(&self) -> &O {
+        &self.observer
     }
 
-    fn down_map_ptr<F>(&mut self, f: F) -> Option<*const N>
+    /// Returns a mutable reference to the observer.
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    fn down_map_ptr<F>(&mut self, f: F) -> Option<NonNull<N>>
     where
         F: Fn(&'n N, usize) -> Option<&'n N>,
     {
         let idx = self.top().1;
-        let here_ptr = self.get() as *const N;
-        let new_ptr =
-            f(unsafe { here_ptr.as_ref().unwrap() }, idx)? as *const N;
+        let new_ptr = NonNull::from(f(self.get_ref(), idx)?);
         self.top_mut().1 += 1;
         Some(new_ptr)
     }
@@ -51,7 +212,7 @@ impl<'n: 'f, 'f, N: 'n> TreeCursor<'n, 'f, N> {
     {
         let maybe_new_ptr = self.down_map_ptr(f);
         if let &Some(new_ptr) = &maybe_new_ptr {
-            self.stack.push((new_ptr, 0));
+            self.push(new_ptr);
         }
         maybe_new_ptr.is_some()
     }
@@ -67,16 +228,15 @@ impl<'n: 'f, 'f, N: 'n> TreeCursor<'n, 'f, N> {
         F: Fn(&'n N, usize) -> Option<&'n N>,
     {
         let new_ptr = self.down_map_ptr(f)?;
-        Some(Self {
-            root: PhantomData,
-            frozen: PhantomData,
-            stack: vec![(new_ptr, 0)],
-        })
+        self.gave_child();
+        Some(TreeCursor::from_stack(Stack::new((new_ptr, 0))))
     }
 
     /// Resets the active node's "next child" counter to 0.
     pub fn zero(&mut self) {
         self.top_mut().1 = 0;
+        let depth = self.depth();
+        self.observer.on_move(Move::Zero { depth });
     }
 
     /// Moves the cursor up one node. Returns true if there was a node to move
@@ -85,43 +245,164 @@ impl<'n: 'f, 'f, N: 'n> TreeCursor<'n, 'f, N> {
     ///
     /// [`zero`]: TreeCursor::zero
     pub fn up(&mut self) -> bool {
-        if self.stack.len() == 1 {
-            self.stack[0].1 = 0;
-            false
-        } else {
-            self.stack.pop().unwrap();
+        if self.pop().is_some() {
             true
+        } else {
+            self.zero();
+            false
+        }
+    }
+
+    /// Moves the cursor up to the root and returns how many nodes it moved
+    /// up. Unlike calling [`up`] until it returns false, this leaves the
+    /// root's "next child" counter alone.
+    ///
+    /// [`up`]: TreeCursor::up
+    pub fn to_root(&mut self) -> usize {
+        let moved = self.depth();
+        self.truncate(1);
+        moved
+    }
+
+    /// Moves the cursor up `n` nodes, as if [`up`] had been called `n` times.
+    /// Returns false without moving if the active node has fewer than `n`
+    /// ancestors. The new active node's "next child" counter is unchanged.
+    ///
+    /// [`up`]: TreeCursor::up
+    pub fn up_n(&mut self, n: usize) -> bool {
+        if n >= self.stack.len() {
+            return false;
+        }
+        let len = self.stack.len() - n;
+        self.truncate(len);
+        true
+    }
+
+    /// Moves the cursor up one node at a time until `pred` returns true for
+    /// the active node, and returns true. The starting node isn't tested. If
+    /// no ancestor matches, the cursor is left at the root and this method
+    /// returns false. Either way, the new active node's "next child" counter
+    /// is unchanged.
+    pub fn up_until<P>(&mut self, mut pred: P) -> bool
+    where
+        P: FnMut(&N) -> bool,
+    {
+        while self.pop().is_some() {
+            if pred(self.get()) {
+                return true;
+            }
         }
+        false
     }
 
     /// Takes the active node from this `TreeCursor` and returns a new
     /// `TreeCursor` at that position. `self` is frozen until the new cursor
     /// goes out of scope.
     pub fn take_cursor<'s>(&'s mut self) -> Option<TreeCursor<'n, 's, N>> {
-        if self.stack.len() == 1 {
-            None
-        } else {
-            let (old_ptr, old_idx) = self.stack.pop().unwrap();
-            let old = unsafe { old_ptr.as_ref().unwrap() };
-            Some(Self {
-                root: PhantomData,
-                frozen: PhantomData,
-                stack: vec![(old, old_idx)],
-            })
+        let top = self.take_top()?;
+        Some(TreeCursor::from_stack(Stack::new(top)))
+    }
+
+    /// Like [`take_cursor`], except that instead of returning the new cursor,
+    /// it passes it to `f` and then rejoins it: `self` ends up wherever the
+    /// new cursor ended up, with the new cursor's "next child" counters. This
+    /// lets a helper navigate a subtree and have `self` continue from where
+    /// the helper stopped. Returns `None` without calling `f` if the active
+    /// node is the root.
+    ///
+    /// [`take_cursor`]: TreeCursor::take_cursor
+    pub fn take_cursor_rejoin<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: for<'s> FnOnce(&mut TreeCursor<'n, 's, N>) -> R,
+    {
+        let top = self.take_top()?;
+        Some(self.rejoin(Stack::new(top), f))
+    }
+
+    fn rejoin<F, R>(&mut self, stack: Stack<N>, f: F) -> R
+    where
+        F: for<'s> FnOnce(&mut TreeCursor<'n, 's, N>) -> R,
+    {
+        let mut child = TreeCursor::from_stack(stack);
+        let r = f(&mut child);
+        for &e in child.stack.as_slice() {
+            self.restore(e);
         }
+        r
     }
 
     /// Returns a shared reference to the active node.
     pub fn get(&self) -> &N {
-        let here: *const N = self.top().0;
-        unsafe { here.as_ref().unwrap() }
+        self.get_ref()
+    }
+
+    /// Returns a shared reference to the active node's parent, or `None` if
+    /// the active node is the root. The cursor doesn't move.
+    ///
+    /// For a cursor made with [`TreeCursorMut::as_cursor`], the root is the
+    /// mutable cursor's active node, so this never reaches that node's
+    /// parent.
+    pub fn peek_parent(&self) -> Option<&N> {
+        let entries = self.stack.as_slice();
+        entries.len().checked_sub(2)
+            .map(|i| unsafe { entries[i].0.as_ref() })
+    }
+
+    /// Returns the index of the active node among its siblings, or `None`
+    /// if it's the root. This is read from the parent's "next child" counter,
+    /// so it's only meaningful if the cursor moved here with [`down`] or
+    /// [`down_nth`] and the parent's counter hasn't changed since.
+    ///
+    /// [`down`]: TreeCursor::down
+    /// [`down_nth`]: TreeCursor::down_nth
+    pub fn index_in_parent(&self) -> Option<usize> {
+        let entries = self.stack.as_slice();
+        entries.len().checked_sub(2).map(|i| entries[i].1 - 1)
+    }
+
+    pub(crate) fn get_ref(&self) -> &'n N {
+        unsafe { self.top().0.as_ref() }
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    pub(crate) fn ptr_path(&self) -> Vec<*const N> {
+        self.stack.as_slice().iter().map(|&(p, _)| p.as_ptr() as *const N)
+            .collect()
+    }
+
+    // Returns the active node's ancestors' counters, as in
+    // `TreeCursorPos::path`.
+    pub(crate) fn counter_path(&self) -> Vec<usize> {
+        let entries = self.stack.as_slice();
+        entries[..entries.len() - 1].iter().map(|&(_, idx)| idx).collect()
+    }
+
+    // Returns true if the active node is the one whose ancestors' counters
+    // are `path`, as in `TreeCursorPos::path`.
+    pub(crate) fn is_at(&self, path: &[usize]) -> bool {
+        let entries = self.stack.as_slice();
+        entries.len() == path.len() + 1
+            && entries.iter().zip(path).all(|(&(_, idx), &p)| idx == p)
+    }
+
+    pub(crate) fn path_eq(&self, path: &[*const N]) -> bool {
+        self.stack.len() == path.len() && self.path_is_prefix_of(path)
+    }
+
+    pub(crate) fn path_is_prefix_of(&self, path: &[*const N]) -> bool {
+        self.stack.len() <= path.len()
+            && self.stack.as_slice().iter().zip(path)
+                .all(|(&(p, _), &q)| ptr::eq(p.as_ptr(), q))
     }
 }
 
-impl<'n: 'f, 'f, N: 'n + Down> TreeCursor<'n, 'f, N> {
-    fn down_ptr(&mut self) -> Option<*const N> {
+impl<'n: 'f, 'f, N: 'n + Down, O: Observer> TreeCursor<'n, 'f, N, O> {
+    fn down_ptr(&mut self) -> Option<NonNull<N>> {
         let idx = self.top().1;
-        let new_ptr = self.get().down(idx)? as *const N;
+        let new_ptr = NonNull::from(self.get_ref().down(idx)?);
         self.top_mut().1 += 1;
         Some(new_ptr)
     }
@@ -134,11 +415,51 @@ impl<'n: 'f, 'f, N: 'n + Down> TreeCursor<'n, 'f, N> {
     pub fn down(&mut self) -> bool {
         let maybe_new_ptr = self.down_ptr();
         if let &Some(new_ptr) = &maybe_new_ptr {
-            self.stack.push((new_ptr, 0));
+            self.push(new_ptr);
         }
         maybe_new_ptr.is_some()
     }
 
+    /// Returns the active node's child at index `idx`, as determined by
+    /// [`Down::down`]. Neither the position nor any "next child" counter
+    /// changes.
+    pub fn peek_child(&self, idx: usize) -> Option<&N> {
+        self.get().down(idx)
+    }
+
+    /// Returns the node that [`down`] would move to, without moving or
+    /// changing any "next child" counter.
+    ///
+    /// [`down`]: TreeCursor::down
+    pub fn peek_next_child(&self) -> Option<&N> {
+        self.peek_child(self.top().1)
+    }
+
+    // Moves down along `path`, a list of counters like `TreeCursorPos::path`,
+    // leaving each node's counter as `down` leaves it. Returns false if a
+    // node on the path is missing.
+    pub(crate) fn follow_path(&mut self, path: &[usize]) -> bool {
+        for &idx in path {
+            match idx.checked_sub(1) {
+                Some(idx) => self.set_counter(idx),
+                None => return false,
+            }
+            if !self.down() {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub(crate) fn has_next_sibling(&self) -> bool {
+        let entries = self.stack.as_slice();
+        if entries.len() < 2 {
+            return false;
+        }
+        let (parent, idx) = entries[entries.len() - 2];
+        unsafe { parent.as_ref() }.down(idx).is_some()
+    }
+
     /// Like [`down`], except instead of moving the position of `self`, it
     /// returns a new `TreeCursor` whose root is the new position. `self` is
     /// frozen until the new cursor goes out of scope.
@@ -146,62 +467,423 @@ impl<'n: 'f, 'f, N: 'n + Down> TreeCursor<'n, 'f, N> {
     /// [`down`]: TreeCursor::down
     pub fn down_take_cursor<'s>(&'s mut self) -> Option<TreeCursor<'n, 's, N>> {
         let new_ptr = self.down_ptr()?;
-        Some(Self {
-            root: PhantomData,
-            frozen: PhantomData,
-            stack: vec![(new_ptr, 0)],
-        })
+        self.gave_child();
+        Some(TreeCursor::from_stack(Stack::new((new_ptr, 0))))
+    }
+
+    /// Like [`take_cursor_rejoin`], except that the new cursor starts at the
+    /// node [`down`] would move to. Returns `None` without calling `f` if
+    /// there's no such node.
+    ///
+    /// [`take_cursor_rejoin`]: TreeCursor::take_cursor_rejoin
+    /// [`down`]: TreeCursor::down
+    pub fn down_take_cursor_rejoin<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: for<'s> FnOnce(&mut TreeCursor<'n, 's, N>) -> R,
+    {
+        let new_ptr = self.down_ptr()?;
+        self.gave_child();
+        Some(self.rejoin(Stack::new((new_ptr, 0)), f))
+    }
+
+    /// Calls [`down`] as long as `pred` returns true for the active node, and
+    /// returns how many nodes the cursor moved down. `pred` is called on the
+    /// starting node first. The cursor stops at the first node that `pred`
+    /// rejects or that has no next child.
+    ///
+    /// [`down`]: TreeCursor::down
+    pub fn down_while<P>(&mut self, mut pred: P) -> usize
+    where
+        P: FnMut(&N) -> bool,
+    {
+        let mut moved = 0;
+        while pred(self.get()) && self.down() {
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Moves the cursor to the first node after the active node's subtree in
+    /// pre-order, by calling [`up`] and then [`down`] until `down` succeeds.
+    /// The active node's remaining children are skipped, and each ancestor's
+    /// "next child" counter decides which of its children comes next.
+    ///
+    /// Returns false if there's no such node. The cursor is then left at the
+    /// root with its counter reset, as [`up`] leaves it.
+    ///
+    /// [`up`]: TreeCursor::up
+    /// [`down`]: TreeCursor::down
+    pub fn skip_subtree(&mut self) -> bool {
+        while self.up() {
+            if self.down() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Moves the cursor to the next node in pre-order: the active node's
+    /// next child if it has one (as with [`down`]), and otherwise the node
+    /// after its subtree (as with [`skip_subtree`]). Starting from the root
+    /// of a fresh cursor, repeated calls visit every other node once and then
+    /// return false, leaving the cursor at the root ready to start over.
+    ///
+    /// [`down`]: TreeCursor::down
+    /// [`skip_subtree`]: TreeCursor::skip_subtree
+    pub fn next_preorder(&mut self) -> bool {
+        self.down() || self.skip_subtree()
     }
 }
 
-impl<'n: 'f, 'f, N: 'n> From<TreeCursorMut<'n, 'f, N>>
-    for TreeCursor<'n, 'f, N>
+impl<'n: 'f, 'f, N, O: Observer> TreeCursor<'n, 'f, N, O>
+where
+    N: 'n + Down + ExactSizeDown,
 {
-    fn from(mut cm: TreeCursorMut<'n, 'f, N>) -> Self {
+    /// Moves the cursor down to the active node's child at index `n`, and
+    /// sets the old active node's "next child" counter to `n + 1`, as if
+    /// [`down`] had been called with the counter at `n`. Returns false
+    /// without doing anything if there's no such child.
+    ///
+    /// [`down`]: TreeCursor::down
+    pub fn down_nth(&mut self, n: usize) -> bool {
+        if n >= self.get().child_count() {
+            return false;
+        }
+        self.set_counter(n);
+        self.down()
+    }
+
+    /// Moves the cursor down to the active node's last child, like
+    /// [`down_nth`]. Returns false if the active node has no children.
+    ///
+    /// [`down_nth`]: TreeCursor::down_nth
+    pub fn down_last(&mut self) -> bool {
+        match self.get().child_count() {
+            0 => false,
+            count => self.down_nth(count - 1),
+        }
+    }
+
+    /// Like [`down`], except that children are visited from last to first:
+    /// with the counter at 0 this moves to the last child, and after that
+    /// each call moves to the child before the one visited last.
+    ///
+    /// The counter is left as [`down_nth`] leaves it, one past the index of
+    /// the child moved to, so [`index_in_parent`] and the sibling moves work
+    /// as usual. Since `down` and `down_rev` share the counter, mixing them
+    /// on one node without calling [`zero`] in between is rarely what you
+    /// want.
+    ///
+    /// [`down`]: TreeCursor::down
+    /// [`down_nth`]: TreeCursor::down_nth
+    /// [`index_in_parent`]: TreeCursor::index_in_parent
+    /// [`zero`]: TreeCursor::zero
+    pub fn down_rev(&mut self) -> bool {
+        match self.top().1 {
+            0 => self.down_last(),
+            1 => false,
+            idx => self.down_nth(idx - 2),
+        }
+    }
+
+    /// Moves the cursor to the active node's previous sibling. Returns false
+    /// without doing anything if there's no previous sibling (including when
+    /// the active node is the root). The new active node's "next child"
+    /// counter starts at 0.
+    ///
+    /// Like [`index_in_parent`], this relies on the parent's "next child"
+    /// counter.
+    ///
+    /// [`index_in_parent`]: TreeCursor::index_in_parent
+    pub fn prev_sibling(&mut self) -> bool {
+        let count = match self.peek_parent() {
+            Some(parent) => parent.child_count(),
+            None => return false,
+        };
+        match self.index_in_parent() {
+            Some(idx) if idx > 0 && idx <= count => {
+                self.up();
+                self.down_nth(idx - 1)
+            },
+            _ => false,
+        }
+    }
+
+    /// Moves the cursor to the active node's next sibling. Returns false
+    /// without doing anything if there's no next sibling (including when the
+    /// active node is the root). The new active node's "next child" counter
+    /// starts at 0.
+    ///
+    /// Like [`index_in_parent`], this relies on the parent's "next child"
+    /// counter.
+    ///
+    /// [`index_in_parent`]: TreeCursor::index_in_parent
+    pub fn next_sibling(&mut self) -> bool {
+        if self.is_last_child() {
+            return false;
+        }
+        let idx = self.index_in_parent().unwrap();
+        self.up();
+        self.down_nth(idx + 1)
+    }
+
+    /// Returns true if the active node is its parent's last child, or if
+    /// it's the root.
+    ///
+    /// Like [`index_in_parent`], this relies on the parent's "next child"
+    /// counter.
+    ///
+    /// [`index_in_parent`]: TreeCursor::index_in_parent
+    pub fn is_last_child(&self) -> bool {
+        let entries = self.stack.as_slice();
+        if entries.len() < 2 {
+            return true;
+        }
+        let (parent, idx) = entries[entries.len() - 2];
+        idx >= unsafe { parent.as_ref() }.child_count()
+    }
+
+    /// Moves the cursor to the previous node in pre-order: the last
+    /// descendant of the active node's previous sibling, or its parent if it
+    /// has no previous sibling. Returns false without doing anything if the
+    /// active node is the root.
+    ///
+    /// Counters are left as [`next_preorder`] would leave them on arriving at
+    /// the same node, so the two can be mixed freely.
+    ///
+    /// [`next_preorder`]: TreeCursor::next_preorder
+    pub fn prev_preorder(&mut self) -> bool {
+        if self.index_in_parent().is_none() {
+            return false;
+        }
+        if self.prev_sibling() {
+            while self.down_last() { }
+        } else {
+            self.up();
+            self.zero();
+        }
+        true
+    }
+}
+
+// The result can't keep `'n`: if `cm` came from `take_cursor` or similar,
+// its parent resumes once `'f` ends and may mutate nodes it shares with `cm`.
+impl<'n: 'f, 'f, N: 'n, O> From<TreeCursorMut<'n, 'f, N, O>>
+    for TreeCursor<'f, 'f, N, O>
+{
+    fn from(cm: TreeCursorMut<'n, 'f, N, O>) -> Self {
         TreeCursor {
             root: PhantomData,
             frozen: PhantomData,
-            stack: cm.stack.drain(..).map(|(p, n)| (p as *const N, n))
-                .collect(), // TODO: speed this up
+            stack: cm.stack,
+            observer: cm.observer,
         }
     }
 }
 
 /// A cursor that holds a mutable reference to its tree.
-#[derive(Debug, Eq, Hash, PartialEq)]
-pub struct TreeCursorMut<'n: 'f, 'f, N: 'n> {
+///
+/// `O` is an [`Observer`] that's told about every move the cursor makes. See
+/// the [`observe`](::observe) module.
+// Invariants (these are what make the unsafe code below sound under both
+// Stacked Borrows and Tree Borrows):
+//
+// - `stack[0]` comes from the `&'n mut N` passed to `new`, or from a node
+//   handed out by a parent cursor that stays frozen for `'f`.
+// - Each later entry was derived from the entry below it, by calling
+//   `DownMut::down_mut` or a `down_map` closure on a reborrow of that entry.
+//   So the entries form a chain, each one a child borrow of the one below.
+// - Only the top entry is ever dereferenced. Accessing an entry invalidates
+//   every pointer derived from it, so a lower entry is only used again once
+//   everything above it has been popped.
+// - References returned by `get` and `get_mut` borrow `self`, so none of them
+//   outlive a move of the cursor. `down_map` closures get a reference whose
+//   lifetime is limited to the call, so they can't keep one either.
+// - Shared cursors made from this one (`as_cursor`, `From`) only get the
+//   lifetime of the borrow (or freeze) they were made under, never `'n`.
+// - `as_cursor` only gets the top entry, so it can't reach the active node's
+//   ancestors. Reading an ancestor (or the active node through it) would
+//   freeze the top entry's pointer, and the next `get_mut` would write
+//   through a frozen pointer. `From` gets the whole stack, but it consumes
+//   this cursor, so nothing writes through the stack afterwards.
+#[derive(Debug)]
+pub struct TreeCursorMut<'n: 'f, 'f, N: 'n, O = NoObserver> {
     root: PhantomData<&'n mut N>,
     frozen: PhantomData<&'f ()>,
-    stack: Vec<(*mut N, usize)>,
+    stack: Stack<N>,
+    // Depths of the entries that `down_map` pushed, shallowest first. `pos`
+    // can't lead back to those nodes, since the closure may have picked any
+    // node.
+    mapped: Vec<usize>,
+    observer: O,
 }
 
 impl<'n, N: 'n> TreeCursorMut<'n, 'n, N> {
     /// Creates a new `TreeCursorMut` starting at `root`.
     pub fn new(root: &'n mut N) -> Self {
+        Self::with_observer(root, NoObserver)
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n> TreeCursorMut<'n, 'f, N> {
+    fn from_stack(stack: Stack<N>) -> Self {
         Self {
             root: PhantomData,
             frozen: PhantomData,
-            stack: vec![(root as *mut N, 0)],
+            stack,
+            mapped: Vec::new(),
+            observer: NoObserver,
         }
     }
 }
 
-impl<'n: 'f, 'f, N: 'n> TreeCursorMut<'n, 'f, N> {
-    fn top(&self) -> &(*mut N, usize) {
-        self.stack.last().unwrap()
+impl<'n: 'f, 'f, N: 'n, O> PartialEq for TreeCursorMut<'n, 'f, N, O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.stack == other.stack
     }
+}
+
+impl<'n: 'f, 'f, N: 'n, O> Eq for TreeCursorMut<'n, 'f, N, O> { }
 
-    fn top_mut(&mut self) -> &mut (*mut N, usize) {
-        self.stack.last_mut().unwrap()
+impl<'n: 'f, 'f, N: 'n, O> Hash for TreeCursorMut<'n, 'f, N, O> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.stack.hash(state)
+    }
+}
+
+impl<'n, N: 'n, O: Observer> TreeCursorMut<'n, 'n, N, O> {
+    /// Creates a new `TreeCursorMut` starting at `root`, which reports its
+    /// moves to `observer`.
+    pub fn with_observer(root: &'n mut N, observer: O) -> Self {
+        Self {
+            root: PhantomData,
+            frozen: PhantomData,
+            stack: Stack::new((NonNull::from(root), 0)),
+            mapped: Vec::new(),
+            observer,
+        }
+    }
+
+    /// Moves the cursor to the root of another tree (or to a different node
+    /// in the same tree, which then becomes the root), as if `self` had just
+    /// been created by [`new`]. Unlike creating a new cursor, this reuses any
+    /// memory `self` has allocated for deep trees.
+    ///
+    /// This is reported to the observer as moving up to the root and
+    /// resetting its counter.
+    ///
+    /// [`new`]: TreeCursorMut::new
+    pub fn reset(&mut self, root: &'n mut N) {
+        let from = self.depth();
+        self.stack.reset((NonNull::from(root), 0));
+        self.mapped.clear();
+        self.moved_up(from);
+        self.observer.on_move(Move::Zero { depth: 0 });
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n, O: Observer> TreeCursorMut<'n, 'f, N, O> {
+    fn top(&self) -> &Entry<N> {
+        self.stack.last()
     }
 
-    fn down_map_ptr<F>(&mut self, f: F) -> Option<*mut N>
+    fn top_mut(&mut self) -> &mut Entry<N> {
+        self.stack.last_mut()
+    }
+
+    // Pushes a node that `down_map_ptr` or `down_ptr` just returned.
+    fn push(&mut self, ptr: NonNull<N>) {
+        let idx = self.top().1 - 1;
+        self.stack.push((ptr, 0));
+        let depth = self.depth();
+        self.observer.on_move(Move::Down { depth, idx });
+    }
+
+    // Reports that a node `down_map_ptr` or `down_ptr` just returned was
+    // handed to a new cursor.
+    fn gave_child(&mut self) {
+        let depth = self.depth();
+        let idx = self.top().1 - 1;
+        self.observer.on_move(Move::DownTake { depth, idx });
+    }
+
+    // Pushes an entry for a child of the active node, reported as moving
+    // down to it with `down` and then setting its counter.
+    fn restore(&mut self, e: Entry<N>) {
+        let idx = self.top().1 - 1;
+        self.set_counter(idx);
+        self.top_mut().1 += 1;
+        self.push(e.0);
+        if e.1 != 0 {
+            self.set_counter(e.1);
+        }
+    }
+
+    fn pop(&mut self) -> Option<Entry<N>> {
+        let top = self.stack.pop()?;
+        self.forget_mapped();
+        let depth = self.depth();
+        self.observer.on_move(Move::Up { depth });
+        Some(top)
+    }
+
+    fn take_top(&mut self) -> Option<Entry<N>> {
+        let top = self.stack.pop()?;
+        self.forget_mapped();
+        let depth = self.depth();
+        self.observer.on_move(Move::Take { depth });
+        Some(top)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        let from = self.depth();
+        self.stack.truncate(len);
+        self.forget_mapped();
+        self.moved_up(from);
+    }
+
+    // Returns true if `down_map` pushed the top entry.
+    fn top_is_mapped(&self) -> bool {
+        self.mapped.last() == Some(&self.depth())
+    }
+
+    // Forgets `down_map` moves to depths the cursor has since moved above.
+    fn forget_mapped(&mut self) {
+        let depth = self.depth();
+        while self.mapped.last().is_some_and(|&d| d > depth) {
+            self.mapped.pop();
+        }
+    }
+
+    // Reports a move up from depth `from` to the current depth.
+    fn moved_up(&mut self, from: usize) {
+        for depth in (self.depth()..from).rev() {
+            self.observer.on_move(Move::Up { depth });
+        }
+    }
+
+    pub(crate) fn set_counter(&mut self, idx: usize) {
+        self.top_mut().1 = idx;
+        let depth = self.depth();
+        self.observer.on_move(Move::Set { depth, idx });
+    }
+
+    /// Returns a shared reference to the observer.
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Returns a mutable reference to the observer.
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    fn down_map_ptr<F>(&mut self, f: F) -> Option<NonNull<N>>
     where
-        F: Fn(&'n mut N, usize) -> Option<&'n mut N>,
+        F: Fn(&mut N, usize) -> Option<&mut N>,
     {
         let idx = self.top().1;
-        let here_ptr = self.get_mut() as *mut N;
-        let new_ptr = f(unsafe { here_ptr.as_mut().unwrap() }, idx)? as *mut N;
+        let new_ptr = NonNull::from(f(self.get_mut(), idx)?);
         self.top_mut().1 += 1;
         Some(new_ptr)
     }
@@ -210,13 +892,25 @@ impl<'n: 'f, 'f, N: 'n> TreeCursorMut<'n, 'f, N> {
     /// counter. If `f` returns a node, it's set as the active node, the old
     /// active node's "next child" counter is incremented, and this method
     /// returns true. Otherwise, this method returns false.
+    ///
+    /// Unlike [`TreeCursor::down_map`], the reference passed to `f` only
+    /// lives for the duration of the call, so `f` can't hold on to it.
+    ///
+    /// Positions only record "next child" counters, so after this,
+    /// [`pos`] no longer leads back to the active node (see
+    /// [`pos_is_exact`]).
+    ///
+    /// [`pos`]: TreeCursorMut::pos
+    /// [`pos_is_exact`]: TreeCursorMut::pos_is_exact
     pub fn down_map<F>(&mut self, f: F) -> bool
     where
-        F: Fn(&'n mut N, usize) -> Option<&'n mut N>,
+        F: Fn(&mut N, usize) -> Option<&mut N>,
     {
         let maybe_new_ptr = self.down_map_ptr(f);
         if let &Some(new_ptr) = &maybe_new_ptr {
-            self.stack.push((new_ptr, 0));
+            self.push(new_ptr);
+            let depth = self.depth();
+            self.mapped.push(depth);
         }
         maybe_new_ptr.is_some()
     }
@@ -229,19 +923,18 @@ impl<'n: 'f, 'f, N: 'n> TreeCursorMut<'n, 'f, N> {
     pub fn down_map_take_cursor<'s, F>(&'s mut self, f: F)
         -> Option<TreeCursorMut<'n, 's, N>>
     where
-        F: Fn(&'n mut N, usize) -> Option<&'n mut N>,
+        F: Fn(&mut N, usize) -> Option<&mut N>,
     {
         let new_ptr = self.down_map_ptr(f)?;
-        Some(Self {
-            root: PhantomData,
-            frozen: PhantomData,
-            stack: vec![(new_ptr, 0)],
-        })
+        self.gave_child();
+        Some(TreeCursorMut::from_stack(Stack::new((new_ptr, 0))))
     }
 
     /// Resets the active node's "next child" counter to 0.
     pub fn zero(&mut self) {
         self.top_mut().1 = 0;
+        let depth = self.depth();
+        self.observer.on_move(Move::Zero { depth });
     }
 
     /// Moves the cursor up one node. Returns true if there was a node to move
@@ -250,64 +943,378 @@ impl<'n: 'f, 'f, N: 'n> TreeCursorMut<'n, 'f, N> {
     ///
     /// [`zero`]: TreeCursorMut::zero
     pub fn up(&mut self) -> bool {
-        if self.stack.len() == 1 {
-            self.stack[0].1 = 0;
-            false
-        } else {
-            self.stack.pop().unwrap();
+        if self.pop().is_some() {
             true
+        } else {
+            self.zero();
+            false
         }
     }
 
+    /// Moves the cursor up to the root and returns how many nodes it moved
+    /// up. Unlike calling [`up`] until it returns false, this leaves the
+    /// root's "next child" counter alone.
+    ///
+    /// [`up`]: TreeCursorMut::up
+    pub fn to_root(&mut self) -> usize {
+        let moved = self.depth();
+        self.truncate(1);
+        moved
+    }
+
+    /// Moves the cursor up `n` nodes, as if [`up`] had been called `n` times.
+    /// Returns false without moving if the active node has fewer than `n`
+    /// ancestors. The new active node's "next child" counter is unchanged.
+    ///
+    /// [`up`]: TreeCursorMut::up
+    pub fn up_n(&mut self, n: usize) -> bool {
+        if n >= self.stack.len() {
+            return false;
+        }
+        let len = self.stack.len() - n;
+        self.truncate(len);
+        true
+    }
+
+    /// Moves the cursor up one node at a time until `pred` returns true for
+    /// the active node, and returns true. The starting node isn't tested. If
+    /// no ancestor matches, the cursor is left at the root and this method
+    /// returns false. Either way, the new active node's "next child" counter
+    /// is unchanged.
+    pub fn up_until<P>(&mut self, mut pred: P) -> bool
+    where
+        P: FnMut(&N) -> bool,
+    {
+        while self.pop().is_some() {
+            if pred(self.get()) {
+                return true;
+            }
+        }
+        false
+    }
+
     /// Takes the active node from this `TreeCursorMut` and returns a new
     /// `TreeCursorMut` at that position. `self` is frozen until the new cursor
     /// goes out of scope.
     pub fn take_cursor<'s>(&'s mut self) -> Option<TreeCursorMut<'n, 's, N>> {
-        if self.stack.len() == 1 {
-            None
-        } else {
-            let (old_ptr, old_idx) = self.stack.pop().unwrap();
-            let old = unsafe { old_ptr.as_mut().unwrap() };
-            Some(Self {
-                root: PhantomData,
-                frozen: PhantomData,
-                stack: vec![(old, old_idx)],
-            })
+        let top = self.take_top()?;
+        Some(TreeCursorMut::from_stack(Stack::new(top)))
+    }
+
+    /// Like [`take_cursor`], except that instead of returning the new cursor,
+    /// it passes it to `f` and then rejoins it: `self` ends up wherever the
+    /// new cursor ended up, with the new cursor's "next child" counters. This
+    /// lets a helper navigate a subtree and have `self` continue from where
+    /// the helper stopped. Returns `None` without calling `f` if the active
+    /// node is the root.
+    ///
+    /// [`take_cursor`]: TreeCursorMut::take_cursor
+    pub fn take_cursor_rejoin<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: for<'s> FnOnce(&mut TreeCursorMut<'n, 's, N>) -> R,
+    {
+        let mapped = self.top_is_mapped();
+        let top = self.take_top()?;
+        Some(self.rejoin(Stack::new(top), mapped, f))
+    }
+
+    // Calls `f` with a cursor rooted at `stack`'s only entry, which is a
+    // child of the active node, and then moves `self` to wherever it ended
+    // up. `mapped` says whether `down_map` pushed that entry.
+    fn rejoin<F, R>(&mut self, stack: Stack<N>, mapped: bool, f: F) -> R
+    where
+        F: for<'s> FnOnce(&mut TreeCursorMut<'n, 's, N>) -> R,
+    {
+        let mut child = TreeCursorMut::from_stack(stack);
+        let r = f(&mut child);
+        for (depth, &e) in child.stack.as_slice().iter().enumerate() {
+            self.restore(e);
+            if (depth == 0 && mapped) || child.mapped.contains(&depth) {
+                let depth = self.depth();
+                self.mapped.push(depth);
+            }
         }
+        r
     }
 
     /// Returns a shared reference to the active node.
     pub fn get(&self) -> &N {
-        let here: *const N = self.top().0;
-        (unsafe { here.as_ref() }).unwrap()
+        unsafe { self.top().0.as_ref() }
+    }
+
+    /// Like [`TreeCursor::index_in_parent`].
+    pub fn index_in_parent(&self) -> Option<usize> {
+        let entries = self.stack.as_slice();
+        entries.len().checked_sub(2).map(|i| entries[i].1 - 1)
     }
 
     /// Returns a mutable reference to the active node.
     pub fn get_mut(&mut self) -> &mut N {
-        let here = self.top().0;
-        (unsafe { here.as_mut() }).unwrap()
+        unsafe { self.top_mut().0.as_mut() }
     }
 
-    pub fn as_cursor<'s>(&'s self) -> TreeCursor<'n, 's, N> {
-        TreeCursor {
-            root: PhantomData,
-            frozen: PhantomData,
-            stack: self.stack.iter()
-                .map(|&(p, n)| (p as *const N, n)).collect(),
-        }
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    /// Returns a `TreeCursor` whose root is the active node, with the same
+    /// "next child" counter. `self` is frozen until the new cursor goes out
+    /// of scope, and the nodes it gives access to can't be borrowed for any
+    /// longer than that.
+    ///
+    /// The new cursor can't move above the active node, so it can't be used
+    /// to read the active node's ancestors: reading them while `self` still
+    /// expects to mutate the active node isn't allowed. To get a shared
+    /// cursor over the whole path, convert `self` with `From` instead.
+    pub fn as_cursor<'s>(&'s self) -> TreeCursor<'s, 's, N> {
+        TreeCursor::from_stack(Stack::new(*self.top()))
     }
 }
 
 /// Stores a cursor's position at an earlier point in time.
-pub struct TreeCursorPos(Vec<usize>);
+///
+/// A `TreeCursorPos` is relative to the root of the cursor that produced it.
+/// For a cursor made with [`take_cursor`] or [`down_take_cursor`], that's a
+/// node in the middle of the tree; convert the position to a [`RelativePos`]
+/// and [`join`] it to the position of that node to get a position the parent
+/// cursor can use.
+///
+/// With the `serde` feature enabled, this type can be serialized and
+/// deserialized, and is represented as a sequence of "next child" counters
+/// starting at the root.
+///
+/// [`take_cursor`]: TreeCursorMut::take_cursor
+/// [`down_take_cursor`]: TreeCursorMut::down_take_cursor
+/// [`join`]: TreeCursorPos::join
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TreeCursorPos(pub(crate) Vec<usize>);
+
+/// Orders positions in document order: a node comes after its ancestors and
+/// before its following siblings. Positions of the same node are ordered by
+/// that node's "next child" counter.
+impl Ord for TreeCursorPos {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path().cmp(other.path())
+            .then_with(|| self.0.last().cmp(&other.0.last()))
+    }
+}
+
+impl PartialOrd for TreeCursorPos {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A position relative to a node other than the root: the "next child"
+/// counters along the path from that node down, starting with that node's
+/// own counter.
+///
+/// With the `serde` feature enabled, this type is represented the same way
+/// as [`TreeCursorPos`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RelativePos(Vec<usize>);
+
+impl TreeCursorPos {
+    // The "next child" counters of the node's ancestors, which identify the
+    // node. Each one is one more than the index of the next node on the path.
+    pub(crate) fn path(&self) -> &[usize] {
+        &self.0[..self.0.len() - 1]
+    }
+
+    /// Returns the depth of the node at this position. The root is at depth
+    /// 0.
+    pub fn depth(&self) -> usize {
+        self.0.len() - 1
+    }
+
+    /// Returns the position of the parent of the node at this position, or
+    /// `None` if it's the root. The parent's "next child" counter is left as
+    /// [`down`] leaves it, pointing past the node at `self`.
+    ///
+    /// [`down`]: TreeCursorMut::down
+    pub fn parent(&self) -> Option<TreeCursorPos> {
+        if self.0.len() > 1 {
+            Some(TreeCursorPos(self.path().to_vec()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the node at `self` is a proper ancestor of the node at
+    /// `other`. "Next child" counters of the two nodes themselves are
+    /// ignored.
+    pub fn is_ancestor_of(&self, other: &TreeCursorPos) -> bool {
+        let path = self.path();
+        path.len() < other.path().len() && other.0.starts_with(path)
+    }
 
-impl<'n: 'f, 'f, N: 'n + DownMut> TreeCursorMut<'n, 'f, N> {
+    /// Returns true if the node at `self` is a proper descendant of the node
+    /// at `other`. Equivalent to `other.is_ancestor_of(self)`.
+    pub fn is_descendant_of(&self, other: &TreeCursorPos) -> bool {
+        other.is_ancestor_of(self)
+    }
+
+    /// Returns the position of the deepest node that's an ancestor of (or
+    /// the same node as) both the node at `a` and the node at `b`. Its "next
+    /// child" counter is 0.
+    pub fn common_ancestor(
+        a: &TreeCursorPos,
+        b: &TreeCursorPos,
+    ) -> TreeCursorPos {
+        let len = a.path().iter().zip(b.path())
+            .take_while(|&(x, y)| x == y)
+            .count();
+        let mut v = a.0[..len].to_vec();
+        v.push(0);
+        TreeCursorPos(v)
+    }
+
+    /// Returns the position `rel` describes, where `self` is the position of
+    /// the node `rel` is relative to. The last counter in `self` is replaced
+    /// by the first one in `rel`.
+    pub fn join(&self, rel: &RelativePos) -> TreeCursorPos {
+        let mut v = self.0[..self.0.len() - 1].to_vec();
+        v.extend_from_slice(&rel.0);
+        TreeCursorPos(v)
+    }
+
+    /// Returns `self` relative to `base`, or `None` if `self` isn't at or
+    /// below the node at `base`. This is the inverse of [`join`]: only the
+    /// path to the node at `base` is compared, not its own counter.
+    ///
+    /// [`join`]: TreeCursorPos::join
+    pub fn relative_to(&self, base: &TreeCursorPos) -> Option<RelativePos> {
+        let prefix = base.0.len() - 1;
+        if self.0.len() > prefix && self.0[..prefix] == base.0[..prefix] {
+            Some(RelativePos(self.0[prefix..].to_vec()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Treats a position as relative to the root of the cursor that produced it,
+/// which is how a position from a cursor made with `take_cursor` or
+/// `down_take_cursor` should be treated.
+impl From<TreeCursorPos> for RelativePos {
+    fn from(pos: TreeCursorPos) -> Self {
+        RelativePos(pos.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for TreeCursorPos {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_newtype_struct("TreeCursorPos", &self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TreeCursorPos {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_newtype_struct(
+            "TreeCursorPos",
+            PosVisitor("a TreeCursorPos", TreeCursorPos),
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for RelativePos {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_newtype_struct("RelativePos", &self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RelativePos {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_newtype_struct(
+            "RelativePos",
+            PosVisitor("a RelativePos", RelativePos),
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+struct PosVisitor<T>(&'static str, fn(Vec<usize>) -> T);
+
+#[cfg(feature = "serde")]
+impl<T> PosVisitor<T> {
+    // Every counter but the last one is one more than a child index, so it
+    // can't be 0.
+    fn finish<E: de::Error>(&self, v: Vec<usize>) -> Result<T, E> {
+        match v.split_last() {
+            None => Err(E::invalid_length(0, self)),
+            Some((_, path)) if path.contains(&0) => {
+                Err(E::invalid_value(de::Unexpected::Seq, self))
+            },
+            Some(_) => Ok((self.1)(v)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> Visitor<'de> for PosVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        d: D,
+    ) -> Result<T, D::Error> {
+        self.finish(Vec::deserialize(d)?)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<T, A::Error> {
+        self.finish(Vec::deserialize(
+            de::value::SeqAccessDeserializer::new(seq),
+        )?)
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n + DownMut, O: Observer> TreeCursorMut<'n, 'f, N, O> {
     /// Returns an opaque object that stores the current position of the cursor.
-    /// Pass it to [`set_pos`] to restore that position.
+    /// Pass it to [`set_pos`] to restore that position. See [`pos_is_exact`]
+    /// for when that isn't the same node.
     ///
     /// [`set_pos`]: TreeCursorMut::set_pos
+    /// [`pos_is_exact`]: TreeCursorMut::pos_is_exact
     pub fn pos(&self) -> TreeCursorPos {
-        TreeCursorPos(self.stack.iter().map(|&(_, idx)| idx).collect())
+        TreeCursorPos(
+            self.stack.as_slice().iter().map(|&(_, idx)| idx).collect(),
+        )
+    }
+
+    /// Returns true if [`set_pos`] would move back to the active node from
+    /// the position [`pos`] returns, as long as the tree's shape doesn't
+    /// change in the meantime. That's not the case if the cursor moved down
+    /// with [`down_map`] on the way from the root (or rejoined a cursor that
+    /// did), since positions only record "next child" counters and a
+    /// `down_map` closure may pick any node.
+    ///
+    /// [`set_pos`]: TreeCursorMut::set_pos
+    /// [`pos`]: TreeCursorMut::pos
+    /// [`down_map`]: TreeCursorMut::down_map
+    pub fn pos_is_exact(&self) -> bool {
+        self.mapped.is_empty()
+    }
+
+    /// Returns the position [`down`] would move to, without moving. The
+    /// node there may not exist. This is the position to [`join`] positions
+    /// from a cursor made with [`down_take_cursor`] to.
+    ///
+    /// [`down`]: TreeCursorMut::down
+    /// [`join`]: TreeCursorPos::join
+    /// [`down_take_cursor`]: TreeCursorMut::down_take_cursor
+    pub fn next_child_pos(&self) -> TreeCursorPos {
+        let mut pos = self.pos();
+        *pos.0.last_mut().unwrap() += 1;
+        pos.0.push(0);
+        pos
     }
 
     /// Moves the cursor to the given position, as long as tree mutation hasn't
@@ -324,21 +1331,74 @@ impl<'n: 'f, 'f, N: 'n + DownMut> TreeCursorMut<'n, 'f, N> {
     ///
     /// [`pos`]: TreeCursorMut::pos
     pub fn set_pos(&mut self, pos: &TreeCursorPos) {
-        self.stack.truncate(1);
-        for &idx in pos.0.iter().rev().skip(1).rev() { // TODO: ugly
-            self.top_mut().1 = idx - 1;
+        self.truncate(1);
+        if !self.follow(&pos.0) {
+            panic!("missing node in TreeCursorPos");
+        }
+    }
+
+    // Moves down along a path of "next child" counters, the first of which
+    // is the active node's, and leaves each node's counter as recorded.
+    // Returns false if a node on the path is missing.
+    fn follow(&mut self, counters: &[usize]) -> bool {
+        let (&last, path) = counters.split_last().unwrap();
+        for &idx in path {
+            match idx.checked_sub(1) {
+                Some(idx) => self.set_counter(idx),
+                None => return false,
+            }
             if !self.down() {
-                panic!("missing node in TreeCursorPos");
+                return false;
             }
         }
-        let &idx = pos.0.last().unwrap();
-        self.top_mut().1 = idx;
+        self.set_counter(last);
+        true
     }
 
-    fn down_ptr(&mut self) -> Option<*mut N> {
-        let idx = self.stack.last().unwrap().1;
-        let new_ptr = self.get_mut().down_mut(idx)? as *mut N;
-        self.stack.last_mut().unwrap().1 += 1;
+    /// Calls `f` with a mutable reference to the active node's `n`th
+    /// ancestor (its parent if `n` is 1, or the active node itself if `n` is
+    /// 0), and then moves the cursor back down to where it was, restoring
+    /// every "next child" counter. Returns `None` without calling `f` if the
+    /// active node has fewer than `n` ancestors.
+    ///
+    /// The nodes below the ancestor are found again with [`down`] once `f`
+    /// returns, using their parents' counters like [`set_pos`] does, so this
+    /// only works if the cursor got there by moving with `down` (or
+    /// [`down_nth`] and similar). If it moved with [`down_map`] anywhere
+    /// below the ancestor, this returns `None` without calling `f`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` changes the tree such that the path back down no longer
+    /// exists. The cursor is then left at the deepest node on the path that
+    /// still exists.
+    ///
+    /// [`down`]: TreeCursorMut::down
+    /// [`set_pos`]: TreeCursorMut::set_pos
+    /// [`down_nth`]: TreeCursorMut::down_nth
+    /// [`down_map`]: TreeCursorMut::down_map
+    pub fn with_ancestor_mut<F, R>(&mut self, n: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut N) -> R,
+    {
+        let len = self.stack.len();
+        if n >= len || self.mapped.last().is_some_and(|&d| d >= len - n) {
+            return None;
+        }
+        let counters: Vec<usize> = self.stack.as_slice()[len - n - 1..]
+            .iter().map(|&(_, idx)| idx).collect();
+        self.truncate(len - n);
+        let r = f(self.get_mut());
+        if !self.follow(&counters) {
+            panic!("missing node after with_ancestor_mut");
+        }
+        Some(r)
+    }
+
+    fn down_ptr(&mut self) -> Option<NonNull<N>> {
+        let idx = self.top().1;
+        let new_ptr = NonNull::from(self.get_mut().down_mut(idx)?);
+        self.top_mut().1 += 1;
         Some(new_ptr)
     }
 
@@ -350,7 +1410,7 @@ impl<'n: 'f, 'f, N: 'n + DownMut> TreeCursorMut<'n, 'f, N> {
     pub fn down(&mut self) -> bool {
         let maybe_new_ptr = self.down_ptr();
         if let &Some(new_ptr) = &maybe_new_ptr {
-            self.stack.push((new_ptr, 0));
+            self.push(new_ptr);
         }
         maybe_new_ptr.is_some()
     }
@@ -364,10 +1424,188 @@ impl<'n: 'f, 'f, N: 'n + DownMut> TreeCursorMut<'n, 'f, N> {
         &'s mut self
     ) -> Option<TreeCursorMut<'n, 's, N>> {
         let new_ptr = self.down_ptr()?;
-        Some(Self {
-            root: PhantomData,
-            frozen: PhantomData,
-            stack: vec![(new_ptr, 0)],
-        })
+        self.gave_child();
+        Some(TreeCursorMut::from_stack(Stack::new((new_ptr, 0))))
+    }
+
+    /// Like [`take_cursor_rejoin`], except that the new cursor starts at the
+    /// node [`down`] would move to. Returns `None` without calling `f` if
+    /// there's no such node.
+    ///
+    /// [`take_cursor_rejoin`]: TreeCursorMut::take_cursor_rejoin
+    /// [`down`]: TreeCursorMut::down
+    pub fn down_take_cursor_rejoin<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: for<'s> FnOnce(&mut TreeCursorMut<'n, 's, N>) -> R,
+    {
+        let new_ptr = self.down_ptr()?;
+        self.gave_child();
+        Some(self.rejoin(Stack::new((new_ptr, 0)), false, f))
+    }
+
+    /// Calls [`down`] as long as `pred` returns true for the active node, and
+    /// returns how many nodes the cursor moved down. `pred` is called on the
+    /// starting node first. The cursor stops at the first node that `pred`
+    /// rejects or that has no next child.
+    ///
+    /// [`down`]: TreeCursorMut::down
+    pub fn down_while<P>(&mut self, mut pred: P) -> usize
+    where
+        P: FnMut(&N) -> bool,
+    {
+        let mut moved = 0;
+        while pred(self.get()) && self.down() {
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Moves the cursor to the first node after the active node's subtree in
+    /// pre-order, by calling [`up`] and then [`down`] until `down` succeeds.
+    /// The active node's remaining children are skipped, and each ancestor's
+    /// "next child" counter decides which of its children comes next.
+    ///
+    /// Returns false if there's no such node. The cursor is then left at the
+    /// root with its counter reset, as [`up`] leaves it.
+    ///
+    /// [`up`]: TreeCursorMut::up
+    /// [`down`]: TreeCursorMut::down
+    pub fn skip_subtree(&mut self) -> bool {
+        while self.up() {
+            if self.down() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Moves the cursor to the next node in pre-order: the active node's
+    /// next child if it has one (as with [`down`]), and otherwise the node
+    /// after its subtree (as with [`skip_subtree`]). Starting from the root
+    /// of a fresh cursor, repeated calls visit every other node once and then
+    /// return false, leaving the cursor at the root ready to start over.
+    ///
+    /// [`down`]: TreeCursorMut::down
+    /// [`skip_subtree`]: TreeCursorMut::skip_subtree
+    pub fn next_preorder(&mut self) -> bool {
+        self.down() || self.skip_subtree()
+    }
+}
+
+// There's deliberately no `peek_parent` here. Reading the parent would freeze
+// the top entry's pointer, so the next `get_mut` would be undefined behavior.
+// For the same reason, the cursor `as_cursor` returns is rooted at the active
+// node, and its `peek_parent` returns `None`.
+impl<'n: 'f, 'f, N: 'n + Down, O: Observer> TreeCursorMut<'n, 'f, N, O> {
+    /// Like [`TreeCursor::peek_child`].
+    pub fn peek_child(&self, idx: usize) -> Option<&N> {
+        self.get().down(idx)
+    }
+
+    /// Returns the node that [`down`] would move to (as long as [`Down`] and
+    /// [`DownMut`] agree), without moving or changing any "next child"
+    /// counter.
+    ///
+    /// [`down`]: TreeCursorMut::down
+    pub fn peek_next_child(&self) -> Option<&N> {
+        self.peek_child(self.top().1)
+    }
+}
+
+impl<'n: 'f, 'f, N, O: Observer> TreeCursorMut<'n, 'f, N, O>
+where
+    N: 'n + DownMut + ExactSizeDown,
+{
+    /// Like [`TreeCursor::down_nth`].
+    pub fn down_nth(&mut self, n: usize) -> bool {
+        if n >= self.get().child_count() {
+            return false;
+        }
+        self.set_counter(n);
+        self.down()
+    }
+
+    /// Like [`TreeCursor::down_last`].
+    pub fn down_last(&mut self) -> bool {
+        match self.get().child_count() {
+            0 => false,
+            count => self.down_nth(count - 1),
+        }
+    }
+
+    /// Like [`TreeCursor::down_rev`]. Since the counter is left as
+    /// [`down_nth`] leaves it, [`pos`] records the child that was moved to.
+    ///
+    /// [`down_nth`]: TreeCursorMut::down_nth
+    /// [`pos`]: TreeCursorMut::pos
+    pub fn down_rev(&mut self) -> bool {
+        match self.top().1 {
+            0 => self.down_last(),
+            1 => false,
+            idx => self.down_nth(idx - 2),
+        }
+    }
+
+    /// Like [`TreeCursor::prev_sibling`], except that this returns false
+    /// without moving if the cursor got to the active node with
+    /// [`down_map`]. The parent can't be read while the active node might
+    /// still be mutated, so there's no way to check which of its children
+    /// the active node is.
+    ///
+    /// [`down_map`]: TreeCursorMut::down_map
+    pub fn prev_sibling(&mut self) -> bool {
+        match self.index_in_parent() {
+            Some(idx) if idx > 0 && !self.top_is_mapped() => {
+                self.up();
+                self.down_nth(idx - 1)
+            },
+            _ => false,
+        }
+    }
+
+    /// Like [`prev_sibling`], but for the next sibling. Finding out that
+    /// there isn't one means moving up to the parent and back down, which
+    /// the observer sees, but the cursor ends up at the same node with the
+    /// same "next child" counter.
+    ///
+    /// [`prev_sibling`]: TreeCursorMut::prev_sibling
+    pub fn next_sibling(&mut self) -> bool {
+        let idx = match self.index_in_parent() {
+            Some(idx) if !self.top_is_mapped() => idx,
+            _ => return false,
+        };
+        let counter = self.top().1;
+        self.up();
+        if self.down_nth(idx + 1) {
+            return true;
+        }
+        // `down_map` didn't pick the active node, so it's the parent's child
+        // at `idx`, and this finds it again.
+        if self.down_nth(idx) {
+            self.set_counter(counter);
+        }
+        false
+    }
+
+    /// Moves the cursor to the previous node in pre-order: the last
+    /// descendant of the active node's previous sibling, or its parent if it
+    /// has no previous sibling. Returns false without doing anything if the
+    /// active node is the root.
+    ///
+    /// Counters are left as [`next_preorder`] would leave them on arriving at
+    /// the same node, so the two can be mixed freely.
+    ///
+    /// [`next_preorder`]: TreeCursorMut::next_preorder
+    pub fn prev_preorder(&mut self) -> bool {
+        if self.index_in_parent().is_none() {
+            return false;
+        }
+        if self.prev_sibling() {
+            while self.down_last() { }
+        } else {
+            self.up();
+            self.zero();
+        }
+        true
     }
 }
@@ -0,0 +1,181 @@
+//! Export of trees to the [Graphviz] DOT language.
+//!
+//! Nodes are identified by address, so a node that's reached more than once
+//! is drawn only once. Structural edges, which are the ones followed by
+//! [`Down`], are drawn solid. Link edges, which are supplied separately with
+//! [`Dot::links`], are drawn dashed and don't affect the layout's ranking.
+//!
+//! [Graphviz]: https://graphviz.org/
+//! [`Down`]: ::Down
+//! [`Dot::links`]: Dot::links
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use cursor::TreeCursor;
+use observe::Observer;
+use prelude::*;
+
+const HIGHLIGHT_ATTRS: &str = "color=\"red\", penwidth=\"2\"";
+const LINK_ATTRS: &str = "style=\"dashed\", constraint=\"false\"";
+
+type Attrs = Vec<(String, String)>;
+
+/// Writes a tree as a DOT `digraph`.
+pub struct Dot<'a, N: 'a> {
+    name: String,
+    label: Box<dyn Fn(&N) -> String + 'a>,
+    attrs: Box<dyn Fn(&N) -> Attrs + 'a>,
+    links: Box<dyn Fn(&'a N, usize) -> Option<&'a N> + 'a>,
+    highlight: Option<Vec<*const N>>,
+}
+
+impl<'a, N: 'a + Down> Dot<'a, N> {
+    /// Creates a new `Dot` that uses `label` to produce the label of each
+    /// node.
+    pub fn new<F>(label: F) -> Self
+    where
+        F: Fn(&N) -> String + 'a,
+    {
+        Self {
+            name: "tree".to_string(),
+            label: Box::new(label),
+            attrs: Box::new(|_| Vec::new()),
+            links: Box::new(|_, _| None),
+            highlight: None,
+        }
+    }
+
+    /// Sets the name of the graph. The default is `tree`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Uses `f` to produce extra attributes for each node, as name-value
+    /// pairs. Values are quoted automatically.
+    pub fn attrs<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&N) -> Vec<(String, String)> + 'a,
+    {
+        self.attrs = Box::new(f);
+        self
+    }
+
+    /// Draws a link edge from each node to every node `f` returns for it.
+    /// `f` takes the same arguments as the closure passed to
+    /// [`TreeCursor::down_map`] and is called with increasing indices until
+    /// it returns `None`. Link targets that aren't part of the tree are drawn,
+    /// but their own links aren't followed.
+    ///
+    /// [`TreeCursor::down_map`]: ::cursor::TreeCursor::down_map
+    pub fn links<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&'a N, usize) -> Option<&'a N> + 'a,
+    {
+        self.links = Box::new(f);
+        self
+    }
+
+    /// Highlights the nodes and edges on the path from the root to the active
+    /// node of `cur`. `cur` must have been created at the root of the tree
+    /// that's exported; otherwise nothing is highlighted.
+    pub fn highlight<'n: 'f, 'f, O: Observer>(
+        mut self,
+        cur: &TreeCursor<'n, 'f, N, O>,
+    ) -> Self {
+        self.highlight = Some(cur.ptr_path());
+        self
+    }
+
+    /// Exports the tree rooted at `root` into a new `String`.
+    pub fn render(&self, root: &'a N) -> String {
+        let mut s = String::new();
+        self.write(root, &mut s).unwrap();
+        s
+    }
+
+    /// Exports the tree rooted at `root` into `w`.
+    pub fn write<W: Write>(&self, root: &'a N, w: &mut W) -> fmt::Result {
+        let mut ids = BTreeMap::new();
+        let mut links = Vec::new();
+        let mut parents: Vec<usize> = Vec::new();
+        writeln!(w, "digraph {} {{", quote(&self.name))?;
+        let mut c = TreeCursor::new(root);
+        'outer: loop {
+            let on_path = self.highlight.as_ref()
+                .is_some_and(|h| c.path_is_prefix_of(h));
+            let id = self.node(&mut ids, c.get(), on_path, w)?;
+            if let Some(&parent) = parents.last() {
+                write!(w, "    n{} -> n{}", parent, id)?;
+                if on_path {
+                    write!(w, " [{}]", HIGHLIGHT_ATTRS)?;
+                }
+                writeln!(w, ";")?;
+            }
+            let mut idx = 0;
+            while let Some(target) = (self.links)(c.get_ref(), idx) {
+                links.push((id, target));
+                idx += 1;
+            }
+            parents.push(id);
+            while !c.down() {
+                parents.pop();
+                if !c.up() {
+                    break 'outer;
+                }
+            }
+        }
+        // Link targets are drawn after the walk, so that targets in the tree
+        // are drawn where the walk reaches them, highlighted if they should
+        // be.
+        let mut link_ids = Vec::with_capacity(links.len());
+        for (from, target) in links {
+            link_ids.push((from, self.node(&mut ids, target, false, w)?));
+        }
+        for (from, to) in link_ids {
+            writeln!(w, "    n{} -> n{} [{}];", from, to, LINK_ATTRS)?;
+        }
+        writeln!(w, "}}")
+    }
+
+    fn node<W: Write>(
+        &self,
+        ids: &mut BTreeMap<*const N, usize>,
+        n: &N,
+        on_path: bool,
+        w: &mut W,
+    ) -> Result<usize, fmt::Error> {
+        if let Some(&id) = ids.get(&(n as *const N)) {
+            return Ok(id);
+        }
+        let id = ids.len();
+        ids.insert(n as *const N, id);
+        write!(w, "    n{} [label={}", id, quote(&(self.label)(n)))?;
+        for (name, value) in (self.attrs)(n) {
+            write!(w, ", {}={}", name, quote(&value))?;
+        }
+        if on_path {
+            write!(w, ", {}", HIGHLIGHT_ATTRS)?;
+        }
+        writeln!(w, "];")?;
+        Ok(id)
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut q = String::with_capacity(s.len() + 2);
+    q.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => q.push_str("\\\""),
+            '\\' => q.push_str("\\\\"),
+            '\n' => q.push_str("\\n"),
+            _ => q.push(ch),
+        }
+    }
+    q.push('"');
+    q
+}
@@ -0,0 +1,173 @@
+//! A flat stream of enter/leave events produced by traversing a tree with a
+//! [`TreeCursor`].
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursor;
+//! use tree_cursor::events::{Event, Events};
+//! use tree_cursor::prelude::*;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! let t = Node("foo", vec![
+//!     Node("bar", vec![Node("baz", vec![])]),
+//!     Node("zup", vec![]),
+//! ]);
+//! let mut cur = TreeCursor::new(&t);
+//! let mut events = Events::new(&mut cur);
+//! let mut entered = Vec::new();
+//! while let Some(ev) = events.next() {
+//!     if let Event::Enter(n, _) = ev {
+//!         entered.push(n.0);
+//!         if n.0 == "bar" {
+//!             events.skip_subtree();
+//!         }
+//!     }
+//! }
+//! assert_eq!(entered, ["foo", "bar", "zup"]);
+//! ```
+//!
+//! [`TreeCursor`]: ::cursor::TreeCursor
+
+use cursor::TreeCursor;
+use observe::{NoObserver, Observer};
+use prelude::*;
+
+/// An event emitted by [`Events`]. Depths are relative to the node the
+/// traversal started at, which is at depth 0.
+#[derive(Debug, Eq, Hash, PartialEq)]
+pub enum Event<'n, N: 'n> {
+    /// The traversal has moved down to this node.
+    Enter(&'n N, usize),
+    /// The traversal is about to move up from this node, having visited all
+    /// its children (or skipped them).
+    Leave(&'n N, usize),
+}
+
+impl<'n, N: 'n> Clone for Event<'n, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'n, N: 'n> Copy for Event<'n, N> { }
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum State {
+    Start,
+    Entered,
+    Left,
+    Done,
+}
+
+/// An iterator of [`Event`]s for the subtree rooted at a cursor's active
+/// node. See the [module documentation](self).
+///
+/// The active node's "next child" counter is reset first, so the traversal
+/// always covers the whole subtree. After that, the cursor is driven with
+/// [`down`] and [`up`], so the usual effects on "next child" counters apply.
+/// When the iterator is exhausted, the cursor is back at the node it started
+/// at. If the iterator is dropped early, the cursor is left wherever the
+/// traversal was.
+///
+/// [`down`]: ::cursor::TreeCursor::down
+/// [`up`]: ::cursor::TreeCursor::up
+#[derive(Debug)]
+pub struct Events<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: 'c = NoObserver> {
+    cur: &'c mut TreeCursor<'n, 'f, N, O>,
+    depth: usize,
+    state: State,
+    skip: bool,
+    down: fn(&mut TreeCursor<'n, 'f, N, O>) -> bool,
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N, O: Observer> Events<'c, 'n, 'f, N, O>
+where
+    N: 'n + Down,
+{
+    /// Creates a new `Events` for the subtree rooted at `cur`'s active node.
+    pub fn new(cur: &'c mut TreeCursor<'n, 'f, N, O>) -> Self {
+        Self::with_down(cur, TreeCursor::down)
+    }
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N, O: Observer> Events<'c, 'n, 'f, N, O>
+where
+    N: 'n + Down + ExactSizeDown,
+{
+    /// Like [`new`], except that the cursor is driven with [`down_rev`], so
+    /// each node's children are visited from last to first.
+    ///
+    /// The [`Event::Leave`] events then come in exactly the reverse of the
+    /// order that [`new`] produces [`Event::Enter`] events (reverse document
+    /// order), and vice versa.
+    ///
+    /// [`new`]: Events::new
+    /// [`down_rev`]: ::cursor::TreeCursor::down_rev
+    pub fn new_rev(cur: &'c mut TreeCursor<'n, 'f, N, O>) -> Self {
+        Self::with_down(cur, TreeCursor::down_rev)
+    }
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: Observer> Events<'c, 'n, 'f, N, O> {
+    fn with_down(
+        cur: &'c mut TreeCursor<'n, 'f, N, O>,
+        down: fn(&mut TreeCursor<'n, 'f, N, O>) -> bool,
+    ) -> Self {
+        cur.zero();
+        Self {
+            cur,
+            depth: 0,
+            state: State::Start,
+            skip: false,
+            down,
+        }
+    }
+
+    /// Skips the remaining children of the innermost node that has been
+    /// entered but not left, so that the next event is that node's
+    /// [`Event::Leave`]. Immediately after an [`Event::Enter`], this skips all
+    /// of the entered node's children.
+    pub fn skip_subtree(&mut self) {
+        self.skip = true;
+    }
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: Observer> Iterator
+    for Events<'c, 'n, 'f, N, O>
+{
+    type Item = Event<'n, N>;
+
+    fn next(&mut self) -> Option<Event<'n, N>> {
+        match self.state {
+            State::Start => {
+                self.state = State::Entered;
+                return Some(Event::Enter(self.cur.get_ref(), 0));
+            },
+            State::Entered => (),
+            State::Left => {
+                if self.depth == 0 {
+                    self.state = State::Done;
+                    return None;
+                }
+                assert!(self.cur.up());
+                self.depth -= 1;
+            },
+            State::Done => return None,
+        }
+        if !self.skip && (self.down)(self.cur) {
+            self.depth += 1;
+            self.state = State::Entered;
+            Some(Event::Enter(self.cur.get_ref(), self.depth))
+        } else {
+            self.skip = false;
+            self.state = State::Left;
+            Some(Event::Leave(self.cur.get_ref(), self.depth))
+        }
+    }
+}
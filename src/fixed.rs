@@ -0,0 +1,277 @@
+//! Cursors with a fixed maximum depth, for use without an allocator.
+//!
+//! [`FixedTreeCursor`] and [`FixedTreeCursorMut`] work like [`TreeCursor`]
+//! and [`TreeCursorMut`], except that their stacks are arrays whose size is
+//! set by the const parameter `D`. The root is at depth 0, and the cursor can
+//! go as deep as `D`. Moving down any further fails with [`CapacityError`]
+//! instead of allocating.
+//!
+//! This module is available even without the `alloc` feature.
+//!
+//! ```
+//! use tree_cursor::fixed::{CapacityError, FixedTreeCursor};
+//! use tree_cursor::prelude::*;
+//!
+//! struct Node(&'static str, Option<Box<Node>>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         if idx == 0 { self.1.as_ref().map(|b| &**b) } else { None }
+//!     }
+//! }
+//!
+//! let t = Node("a", Some(Box::new(Node("b", Some(Box::new(
+//!     Node("c", None),
+//! ))))));
+//! let mut cur = FixedTreeCursor::<_, 1>::new(&t);
+//! assert_eq!(cur.down(), Ok(true));
+//! assert_eq!(cur.get().0, "b");
+//! assert_eq!(cur.down(), Err(CapacityError));
+//! assert_eq!(cur.get().0, "b");
+//! ```
+//!
+//! [`TreeCursor`]: ::cursor::TreeCursor
+//! [`TreeCursorMut`]: ::cursor::TreeCursorMut
+
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use prelude::*;
+
+/// The error returned when a fixed-capacity cursor would have to go deeper
+/// than its maximum depth.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cursor is at its maximum depth")
+    }
+}
+
+impl Error for CapacityError { }
+
+// Like `stack::Stack`, but fixed-size. `rest[..len]` holds every entry above
+// the root.
+#[derive(Debug)]
+struct FixedStack<N, const D: usize> {
+    root: (NonNull<N>, usize),
+    rest: [(NonNull<N>, usize); D],
+    len: usize,
+}
+
+impl<N, const D: usize> FixedStack<N, D> {
+    fn new(root: NonNull<N>) -> Self {
+        Self {
+            root: (root, 0),
+            rest: [(root, 0); D],
+            len: 0,
+        }
+    }
+
+    fn top(&self) -> &(NonNull<N>, usize) {
+        match self.len {
+            0 => &self.root,
+            len => &self.rest[len - 1],
+        }
+    }
+
+    fn top_mut(&mut self) -> &mut (NonNull<N>, usize) {
+        match self.len {
+            0 => &mut self.root,
+            len => &mut self.rest[len - 1],
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == D
+    }
+
+    // Panics if the stack is full.
+    fn push(&mut self, p: NonNull<N>) {
+        self.rest[self.len] = (p, 0);
+        self.len += 1;
+    }
+
+    fn up(&mut self) -> bool {
+        if self.len == 0 {
+            self.root.1 = 0;
+            false
+        } else {
+            self.len -= 1;
+            true
+        }
+    }
+}
+
+/// A fixed-capacity cursor that holds a shared reference to its tree. See
+/// the [module documentation](self).
+#[derive(Debug)]
+pub struct FixedTreeCursor<'n, N: 'n, const D: usize> {
+    root: PhantomData<&'n N>,
+    stack: FixedStack<N, D>,
+}
+
+impl<'n, N: 'n, const D: usize> FixedTreeCursor<'n, N, D> {
+    /// Creates a new `FixedTreeCursor` starting at `root`.
+    pub fn new(root: &'n N) -> Self {
+        Self {
+            root: PhantomData,
+            stack: FixedStack::new(NonNull::from(root)),
+        }
+    }
+
+    /// Moves the cursor to a new root, as if `self` had just been created by
+    /// [`new`].
+    ///
+    /// [`new`]: FixedTreeCursor::new
+    pub fn reset(&mut self, root: &'n N) {
+        self.stack = FixedStack::new(NonNull::from(root));
+    }
+
+    /// Returns the depth of the active node. The root is at depth 0.
+    pub fn depth(&self) -> usize {
+        self.stack.len
+    }
+
+    /// Like [`TreeCursor::down_map`], except that if `f` returns a node but
+    /// the cursor is already at its maximum depth, this method returns
+    /// `Err(CapacityError)` and leaves the cursor and its counters unchanged.
+    ///
+    /// [`TreeCursor::down_map`]: ::cursor::TreeCursor::down_map
+    pub fn down_map<F>(&mut self, f: F) -> Result<bool, CapacityError>
+    where
+        F: Fn(&'n N, usize) -> Option<&'n N>,
+    {
+        let idx = self.stack.top().1;
+        match f(self.get_ref(), idx) {
+            None => Ok(false),
+            Some(_) if self.stack.is_full() => Err(CapacityError),
+            Some(new) => {
+                self.stack.top_mut().1 += 1;
+                self.stack.push(NonNull::from(new));
+                Ok(true)
+            },
+        }
+    }
+
+    /// Resets the active node's "next child" counter to 0.
+    pub fn zero(&mut self) {
+        self.stack.top_mut().1 = 0;
+    }
+
+    /// Like [`TreeCursor::up`].
+    ///
+    /// [`TreeCursor::up`]: ::cursor::TreeCursor::up
+    pub fn up(&mut self) -> bool {
+        self.stack.up()
+    }
+
+    /// Returns a shared reference to the active node.
+    pub fn get(&self) -> &N {
+        self.get_ref()
+    }
+
+    fn get_ref(&self) -> &'n N {
+        unsafe { self.stack.top().0.as_ref() }
+    }
+}
+
+impl<'n, N: 'n + Down, const D: usize> FixedTreeCursor<'n, N, D> {
+    /// Like [`TreeCursor::down`], except that if there's a node to move to
+    /// but the cursor is already at its maximum depth, this method returns
+    /// `Err(CapacityError)` and leaves the cursor and its counters unchanged.
+    ///
+    /// [`TreeCursor::down`]: ::cursor::TreeCursor::down
+    pub fn down(&mut self) -> Result<bool, CapacityError> {
+        self.down_map(|n, idx| n.down(idx))
+    }
+}
+
+/// A fixed-capacity cursor that holds a mutable reference to its tree. See
+/// the [module documentation](self).
+// The invariants are the same as `TreeCursorMut`'s.
+#[derive(Debug)]
+pub struct FixedTreeCursorMut<'n, N: 'n, const D: usize> {
+    root: PhantomData<&'n mut N>,
+    stack: FixedStack<N, D>,
+}
+
+impl<'n, N: 'n, const D: usize> FixedTreeCursorMut<'n, N, D> {
+    /// Creates a new `FixedTreeCursorMut` starting at `root`.
+    pub fn new(root: &'n mut N) -> Self {
+        Self {
+            root: PhantomData,
+            stack: FixedStack::new(NonNull::from(root)),
+        }
+    }
+
+    /// Moves the cursor to a new root, as if `self` had just been created by
+    /// [`new`].
+    ///
+    /// [`new`]: FixedTreeCursorMut::new
+    pub fn reset(&mut self, root: &'n mut N) {
+        self.stack = FixedStack::new(NonNull::from(root));
+    }
+
+    /// Returns the depth of the active node. The root is at depth 0.
+    pub fn depth(&self) -> usize {
+        self.stack.len
+    }
+
+    /// Like [`TreeCursorMut::down_map`], except that if `f` returns a node
+    /// but the cursor is already at its maximum depth, this method returns
+    /// `Err(CapacityError)` and leaves the cursor and its counters unchanged.
+    ///
+    /// [`TreeCursorMut::down_map`]: ::cursor::TreeCursorMut::down_map
+    pub fn down_map<F>(&mut self, f: F) -> Result<bool, CapacityError>
+    where
+        F: Fn(&mut N, usize) -> Option<&mut N>,
+    {
+        let idx = self.stack.top().1;
+        let full = self.stack.is_full();
+        match f(self.get_mut(), idx).map(NonNull::from) {
+            None => Ok(false),
+            Some(_) if full => Err(CapacityError),
+            Some(new) => {
+                self.stack.top_mut().1 += 1;
+                self.stack.push(new);
+                Ok(true)
+            },
+        }
+    }
+
+    /// Resets the active node's "next child" counter to 0.
+    pub fn zero(&mut self) {
+        self.stack.top_mut().1 = 0;
+    }
+
+    /// Like [`TreeCursorMut::up`].
+    ///
+    /// [`TreeCursorMut::up`]: ::cursor::TreeCursorMut::up
+    pub fn up(&mut self) -> bool {
+        self.stack.up()
+    }
+
+    /// Returns a shared reference to the active node.
+    pub fn get(&self) -> &N {
+        unsafe { self.stack.top().0.as_ref() }
+    }
+
+    /// Returns a mutable reference to the active node.
+    pub fn get_mut(&mut self) -> &mut N {
+        unsafe { self.stack.top_mut().0.as_mut() }
+    }
+}
+
+impl<'n, N: 'n + DownMut, const D: usize> FixedTreeCursorMut<'n, N, D> {
+    /// Like [`TreeCursorMut::down`], except that if there's a node to move to
+    /// but the cursor is already at its maximum depth, this method returns
+    /// `Err(CapacityError)` and leaves the cursor and its counters unchanged.
+    ///
+    /// [`TreeCursorMut::down`]: ::cursor::TreeCursorMut::down
+    pub fn down(&mut self) -> Result<bool, CapacityError> {
+        self.down_map(|n, idx| n.down_mut(idx))
+    }
+}
@@ -0,0 +1,167 @@
+//! Iterators over the leaves, the nodes at one depth, and the root-to-leaf
+//! paths of a tree.
+//!
+//! These are built on [`Events`], so like it they traverse the whole subtree
+//! rooted at a [`TreeCursor`]'s active node without recursion, and leave the
+//! cursor back at that node once they're exhausted.
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursor;
+//! use tree_cursor::prelude::*;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! let t = Node("foo", vec![
+//!     Node("bar", vec![Node("baz", vec![])]),
+//!     Node("zup", vec![]),
+//! ]);
+//! let mut cur = TreeCursor::new(&t);
+//! let leaves: Vec<_> = cur.leaves().map(|n| n.0).collect();
+//! assert_eq!(leaves, ["baz", "zup"]);
+//! let level: Vec<_> = cur.at_depth(1).map(|n| n.0).collect();
+//! assert_eq!(level, ["bar", "zup"]);
+//! let paths: Vec<Vec<_>> = cur.paths()
+//!     .map(|p| p.iter().map(|n| n.0).collect())
+//!     .collect();
+//! assert_eq!(paths, [vec!["foo", "bar", "baz"], vec!["foo", "zup"]]);
+//! ```
+//!
+//! [`Events`]: ::events::Events
+//! [`TreeCursor`]: ::cursor::TreeCursor
+
+use alloc::vec::Vec;
+use cursor::TreeCursor;
+use events::{Event, Events};
+use observe::{NoObserver, Observer};
+use prelude::*;
+
+impl<'n: 'f, 'f, N: 'n + Down, O: Observer> TreeCursor<'n, 'f, N, O> {
+    /// Returns an iterator over the leaves of the subtree rooted at the
+    /// active node. See the [`iter`](::iter) module.
+    pub fn leaves<'c>(&'c mut self) -> Leaves<'c, 'n, 'f, N, O> {
+        Leaves {
+            events: Events::new(self),
+            entered: false,
+        }
+    }
+
+    /// Returns an iterator over the nodes `depth` levels below the active
+    /// node, in order. The active node itself is at depth 0. Nothing below
+    /// `depth` is visited. See the [`iter`](::iter) module.
+    pub fn at_depth<'c>(
+        &'c mut self,
+        depth: usize,
+    ) -> AtDepth<'c, 'n, 'f, N, O> {
+        AtDepth {
+            events: Events::new(self),
+            depth,
+        }
+    }
+
+    /// Returns an iterator over the paths from the active node to each leaf
+    /// of its subtree, in order. See the [`iter`](::iter) module.
+    pub fn paths<'c>(&'c mut self) -> Paths<'c, 'n, 'f, N, O> {
+        Paths {
+            events: Events::new(self),
+            path: Vec::new(),
+            entered: false,
+        }
+    }
+}
+
+/// An iterator over the leaves of a subtree, returned by
+/// [`TreeCursor::leaves`].
+#[derive(Debug)]
+pub struct Leaves<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: 'c = NoObserver> {
+    events: Events<'c, 'n, 'f, N, O>,
+    entered: bool,
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: Observer> Iterator
+    for Leaves<'c, 'n, 'f, N, O>
+{
+    type Item = &'n N;
+
+    fn next(&mut self) -> Option<&'n N> {
+        loop {
+            match self.events.next()? {
+                Event::Enter(..) => self.entered = true,
+                Event::Leave(node, _) => {
+                    if self.entered {
+                        self.entered = false;
+                        return Some(node);
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// An iterator over the nodes at one depth of a subtree, returned by
+/// [`TreeCursor::at_depth`].
+#[derive(Debug)]
+pub struct AtDepth<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: 'c = NoObserver> {
+    events: Events<'c, 'n, 'f, N, O>,
+    depth: usize,
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: Observer> Iterator
+    for AtDepth<'c, 'n, 'f, N, O>
+{
+    type Item = &'n N;
+
+    fn next(&mut self) -> Option<&'n N> {
+        loop {
+            if let Event::Enter(node, depth) = self.events.next()? {
+                if depth == self.depth {
+                    self.events.skip_subtree();
+                    return Some(node);
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over root-to-leaf paths, returned by [`TreeCursor::paths`].
+/// Each path starts with the node the iterator was created at.
+#[derive(Debug)]
+pub struct Paths<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: 'c = NoObserver> {
+    events: Events<'c, 'n, 'f, N, O>,
+    path: Vec<&'n N>,
+    entered: bool,
+}
+
+impl<'c, 'n: 'f + 'c, 'f: 'c, N: 'n, O: Observer> Iterator
+    for Paths<'c, 'n, 'f, N, O>
+{
+    type Item = Vec<&'n N>;
+
+    fn next(&mut self) -> Option<Vec<&'n N>> {
+        loop {
+            match self.events.next()? {
+                Event::Enter(node, _) => {
+                    self.path.push(node);
+                    self.entered = true;
+                },
+                Event::Leave(..) => {
+                    let leaf = if self.entered {
+                        self.entered = false;
+                        Some(self.path.clone())
+                    } else {
+                        None
+                    };
+                    self.path.pop();
+                    if leaf.is_some() {
+                        return leaf;
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Undo, redo, and transactional rollback of edits made through a
+//! [`TreeCursorMut`].
+//!
+//! Edits are values of a type implementing [`Edit`], and applying one yields
+//! the edit that reverts it. [`Journal`] applies edits to the active node,
+//! records each inverse along with the [`TreeCursorPos`] it was applied at,
+//! and replays inverses to undo.
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursorMut;
+//! use tree_cursor::journal::{Edit, Journal};
+//! use tree_cursor::prelude::*;
+//!
+//! struct Node(i32, Vec<Node>);
+//!
+//! impl DownMut for Node {
+//!     fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+//!         self.1.get_mut(idx)
+//!     }
+//! }
+//!
+//! struct Set(i32);
+//!
+//! impl Edit<Node> for Set {
+//!     fn apply(self, node: &mut Node) -> Self {
+//!         Set(std::mem::replace(&mut node.0, self.0))
+//!     }
+//! }
+//!
+//! let mut t = Node(0, vec![Node(1, vec![])]);
+//! {
+//!     let mut j = Journal::new(TreeCursorMut::new(&mut t));
+//!     assert!(j.cursor().down());
+//!     j.apply(Set(10));
+//!     assert!(j.cursor().up());
+//!     j.apply(Set(20));
+//!     assert!(j.undo());
+//!     assert!(j.undo());
+//!     assert!(!j.undo());
+//!     assert!(j.redo());
+//!     assert_eq!(j.get().0, 10);
+//! }
+//! assert_eq!((t.0, t.1[0].0), (0, 10));
+//! ```
+//!
+//! [`TreeCursorMut`]: ::cursor::TreeCursorMut
+//! [`TreeCursorPos`]: ::cursor::TreeCursorPos
+
+use alloc::vec::Vec;
+use cursor::{TreeCursorMut, TreeCursorPos};
+use observe::{NoObserver, Observer};
+use prelude::*;
+
+/// An invertible edit to a single node.
+pub trait Edit<N>: Sized {
+    /// Applies this edit to `node` and returns the edit that reverts it.
+    fn apply(self, node: &mut N) -> Self;
+}
+
+#[derive(Debug)]
+struct Group<E> {
+    ops: Vec<(TreeCursorPos, E)>,
+    before: TreeCursorPos,
+    after: TreeCursorPos,
+}
+
+/// Wraps a [`TreeCursorMut`] and records the [`Edit`]s applied through it.
+/// See the [module documentation](self).
+///
+/// Each call to [`apply`] outside a transaction, and each outermost
+/// transaction, forms one step of history. Undoing or redoing a step moves the
+/// cursor to each edit's position in turn, and then restores the position the
+/// cursor had before the step (for undo) or after it (for redo).
+///
+/// Changes made directly through [`cursor`] aren't recorded. If they change
+/// the tree's shape, recorded positions may no longer be valid.
+///
+/// [`TreeCursorMut`]: ::cursor::TreeCursorMut
+/// [`apply`]: Journal::apply
+/// [`cursor`]: Journal::cursor
+#[derive(Debug)]
+pub struct Journal<'n: 'f, 'f, N: 'n, E, O = NoObserver> {
+    cur: TreeCursorMut<'n, 'f, N, O>,
+    undo: Vec<Group<E>>,
+    redo: Vec<Group<E>>,
+    txn: Option<Group<E>>,
+    marks: Vec<(usize, TreeCursorPos)>,
+}
+
+impl<'n: 'f, 'f, N, E, O> Journal<'n, 'f, N, E, O>
+where
+    N: 'n + DownMut,
+    E: Edit<N>,
+    O: Observer,
+{
+    /// Creates a new `Journal` with empty history.
+    pub fn new(cur: TreeCursorMut<'n, 'f, N, O>) -> Self {
+        Self {
+            cur,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            txn: None,
+            marks: Vec::new(),
+        }
+    }
+
+    /// Returns the wrapped cursor, for navigation.
+    pub fn cursor(&mut self) -> &mut TreeCursorMut<'n, 'f, N, O> {
+        &mut self.cur
+    }
+
+    /// Returns a shared reference to the active node.
+    pub fn get(&self) -> &N {
+        self.cur.get()
+    }
+
+    /// Unwraps the cursor, discarding all history.
+    pub fn into_inner(self) -> TreeCursorMut<'n, 'f, N, O> {
+        self.cur
+    }
+
+    /// Applies `edit` to the active node and records it. Discards anything
+    /// that could be redone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor's position can't be recorded, because it moved
+    /// down with [`down_map`] on the way to the active node (see
+    /// [`pos_is_exact`]). Undoing the edit would then revert some other node.
+    ///
+    /// [`down_map`]: ::cursor::TreeCursorMut::down_map
+    /// [`pos_is_exact`]: ::cursor::TreeCursorMut::pos_is_exact
+    pub fn apply(&mut self, edit: E) {
+        let pos = self.pos();
+        let inverse = edit.apply(self.cur.get_mut());
+        self.redo.clear();
+        match self.txn {
+            Some(ref mut g) => {
+                g.ops.push((pos.clone(), inverse));
+                g.after = pos;
+            },
+            None => self.undo.push(Group {
+                ops: vec![(pos.clone(), inverse)],
+                before: pos.clone(),
+                after: pos,
+            }),
+        }
+    }
+
+    /// Starts a transaction. Transactions may be nested; only the outermost
+    /// one becomes a step of history when committed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor's position can't be recorded, as with [`apply`].
+    ///
+    /// [`apply`]: Journal::apply
+    pub fn begin(&mut self) {
+        let pos = self.pos();
+        match self.txn {
+            Some(ref g) => self.marks.push((g.ops.len(), pos)),
+            None => {
+                self.txn = Some(Group {
+                    ops: Vec::new(),
+                    before: pos.clone(),
+                    after: pos.clone(),
+                });
+                self.marks.push((0, pos));
+            },
+        }
+    }
+
+    /// Returns true if a transaction is in progress.
+    pub fn in_transaction(&self) -> bool {
+        self.txn.is_some()
+    }
+
+    /// Commits the innermost transaction. If it's the outermost one and it
+    /// applied any edits, they become one step of history.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transaction is in progress.
+    pub fn commit(&mut self) {
+        self.marks.pop().expect("no transaction in progress");
+        if self.marks.is_empty() {
+            let g = self.txn.take().unwrap();
+            if !g.ops.is_empty() {
+                self.undo.push(g);
+            }
+        }
+    }
+
+    /// Reverts every edit applied since the innermost transaction began,
+    /// restores the cursor position it began at, and ends it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transaction is in progress, or if a recorded position is
+    /// no longer valid.
+    pub fn rollback(&mut self) {
+        let (mark, restore) =
+            self.marks.pop().expect("no transaction in progress");
+        let mut g = self.txn.take().unwrap();
+        let mut reverted = g.ops.split_off(mark);
+        while let Some((pos, inverse)) = reverted.pop() {
+            self.cur.set_pos(&pos);
+            inverse.apply(self.cur.get_mut());
+        }
+        self.cur.set_pos(&restore);
+        if !self.marks.is_empty() {
+            // Put `after` back to what it was when the inner transaction
+            // began: the position of the last edit that's left, or where the
+            // outer transaction began if there's none.
+            g.after = match g.ops.last() {
+                Some((pos, _)) => pos.clone(),
+                None => g.before.clone(),
+            };
+            self.txn = Some(g);
+        }
+    }
+
+    /// Undoes the most recent step of history. Returns false if there was
+    /// nothing to undo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction is in progress, or if a recorded position is
+    /// no longer valid.
+    pub fn undo(&mut self) -> bool {
+        assert!(self.txn.is_none(), "transaction in progress");
+        match self.undo.pop() {
+            Some(g) => {
+                let g = self.replay(g, true);
+                self.redo.push(g);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Redoes the most recently undone step of history. Returns false if
+    /// there was nothing to redo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction is in progress, or if a recorded position is
+    /// no longer valid.
+    pub fn redo(&mut self) -> bool {
+        assert!(self.txn.is_none(), "transaction in progress");
+        match self.redo.pop() {
+            Some(g) => {
+                let g = self.replay(g, false);
+                self.undo.push(g);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns true if there's a step of history that can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there's a step of history that can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn pos(&self) -> TreeCursorPos {
+        assert!(
+            self.cur.pos_is_exact(),
+            "cursor moved with down_map, so its position can't be recorded",
+        );
+        self.cur.pos()
+    }
+
+    fn replay(&mut self, g: Group<E>, backward: bool) -> Group<E> {
+        let Group { mut ops, before, after } = g;
+        if backward {
+            ops.reverse();
+        }
+        let mut inverses: Vec<_> = ops.into_iter().map(|(pos, edit)| {
+            self.cur.set_pos(&pos);
+            let inverse = edit.apply(self.cur.get_mut());
+            (pos, inverse)
+        }).collect();
+        if backward {
+            inverses.reverse();
+            self.cur.set_pos(&before);
+        } else {
+            self.cur.set_pos(&after);
+        }
+        Group { ops: inverses, before, after }
+    }
+}
@@ -5,16 +5,58 @@
 //!
 //! # Soundness
 //!
-//! **In a nutshell: I don't know if this crate is sound. I *think* it is, but
-//! you should still check it yourself before using it.**
+//! **In a nutshell: the unsafe code in this crate is checked with Miri under
+//! both Stacked Borrows and Tree Borrows, but it hasn't been formally proven
+//! sound.**
 //!
 //! The current implementation uses unsafe code to work around the borrow
-//! checker's strictness about lifetimes. So far, I haven't formally proven
-//! that it's memory-safe, especially in the presence of malicious code. I've
-//! done my best to write adversarial tests, and I'll continue poking at corner
-//! cases until I'm personally satisfied that it's sound, but it's not likely
-//! I'll get around to formally proving it until I better understand the borrow
-//! checker.
+//! checker's strictness about lifetimes. The invariants that unsafe code
+//! relies on are written down next to [`TreeCursorMut`]'s definition, and the
+//! test suite includes adversarial tests that are meant to be run under Miri:
+//!
+//! ```text
+//! cargo +nightly miri test
+//! MIRIFLAGS=-Zmiri-tree-borrows cargo +nightly miri test
+//! ```
+//!
+//! Three of those invariants show up in the API. First, a closure passed to
+//! [`TreeCursorMut::down_map`] can't keep the node it's given:
+//!
+// The examples use `cursor`, which needs the `alloc` feature.
+#![cfg_attr(feature = "alloc", doc = "```compile_fail,E0521")]
+#![cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+//! # use tree_cursor::cursor::TreeCursorMut;
+//! # use std::cell::Cell;
+//! let mut root = 0;
+//! let stash = Cell::new(None);
+//! let mut cur = TreeCursorMut::new(&mut root);
+//! cur.down_map(|n, _| { stash.set(Some(n)); None });
+//! ```
+//!
+//! Second, a [`TreeCursor`] made from a [`TreeCursorMut`] can't hand out
+//! references that outlive the mutable cursor's borrow:
+//!
+#![cfg_attr(feature = "alloc", doc = "```compile_fail,E0502")]
+#![cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+//! # use tree_cursor::cursor::TreeCursorMut;
+//! # use std::cell::Cell;
+//! let mut root = 0;
+//! let stash = Cell::new(None);
+//! let mut cur = TreeCursorMut::new(&mut root);
+//! cur.as_cursor().down_map(|n, _| { stash.set(Some(n)); None });
+//! *cur.get_mut() += 1;
+//! stash.get();
+//! ```
+//!
+//! Third, a cursor made with [`TreeCursorMut::as_cursor`] is rooted at the
+//! mutable cursor's active node, so it can't read that node's ancestors.
+//! Doing so would invalidate the mutable cursor's pointer to the active node.
+//!
+//! # Features
+//!
+//! This crate is `no_std`. Everything except [`fixed`] needs an allocator and
+//! is behind the `alloc` feature, which is enabled by default. The `serde`
+//! feature implies `alloc`.
 //!
 //! # Concepts
 //!
@@ -30,7 +72,8 @@
 //!
 //! Let's look at a simple tree and how you might use a cursor to traverse it.
 //!
-//! ```
+#![cfg_attr(feature = "alloc", doc = "```")]
+#![cfg_attr(not(feature = "alloc"), doc = "```ignore")]
 //! use tree_cursor::cursor::TreeCursor;
 //! use tree_cursor::prelude::*;
 //!
@@ -87,7 +130,8 @@
 //! Here are two ways you might do a full traversal if you don't know the
 //! tree's exact shape ahead of time:
 //!
-//! ```
+#![cfg_attr(feature = "alloc", doc = "```")]
+#![cfg_attr(not(feature = "alloc"), doc = "```ignore")]
 //! # use tree_cursor::cursor::TreeCursor;
 //! # use tree_cursor::prelude::*;
 //! #
@@ -133,21 +177,72 @@
 //! use [`TreeCursorMut`] instead, which gives you access to a mutable
 //! reference to the active node.
 //!
-//! [`Cell`]: std::cell::Cell
-//! [`RefCell`]: std::cell::RefCell
+//! [`Cell`]: core::cell::Cell
+//! [`RefCell`]: core::cell::RefCell
 //!
 //! [`TreeCursor`]: cursor::TreeCursor
 //! [`TreeCursorMut`]: cursor::TreeCursorMut
+//! [`TreeCursorMut::down_map`]: cursor::TreeCursorMut::down_map
+//! [`TreeCursorMut::as_cursor`]: cursor::TreeCursorMut::as_cursor
 //! [`down`]: cursor::TreeCursor::down
 //! [`down_map`]: cursor::TreeCursor::down_map
 //! [`up`]: cursor::TreeCursor::up
 //! [`get`]: cursor::TreeCursor::get
 //! [`get_mut`]: cursor::TreeCursorMut::get_mut
 
+#![cfg_attr(not(test), no_std)]
+// Most of the docs link to the cursors in `cursor`, which only exists with
+// the `alloc` feature. Without it, those links have nothing to point to;
+// with it, they're all checked.
+#![cfg_attr(
+    not(feature = "alloc"),
+    allow(rustdoc::broken_intra_doc_links),
+)]
+
+#[cfg(test)]
+extern crate core;
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+#[cfg(feature = "alloc")]
+pub mod build;
+#[cfg(feature = "alloc")]
 pub mod cursor;
+#[cfg(feature = "alloc")]
+pub mod dot;
+#[cfg(feature = "alloc")]
+pub mod events;
+pub mod fixed;
+#[cfg(feature = "alloc")]
+pub mod iter;
+#[cfg(feature = "alloc")]
+pub mod journal;
+#[cfg(feature = "alloc")]
+pub mod observe;
+#[cfg(feature = "alloc")]
+pub mod persistent;
+#[cfg(feature = "alloc")]
+pub mod range;
+#[cfg(feature = "alloc")]
+pub mod render;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "alloc")]
+mod stack;
+#[cfg(feature = "alloc")]
+pub mod summary;
+#[cfg(feature = "alloc")]
+pub mod transform;
+#[cfg(feature = "alloc")]
+pub mod visit;
 
 pub mod prelude {
-    pub use super::{Down, DownMut};
+    pub use super::{Down, DownMut, ExactSizeDown, Up};
 }
 
 #[cfg(test)]
@@ -166,3 +261,33 @@ pub trait DownMut {
     /// [`TreeCursorMut::down`]: cursor::TreeCursorMut::down
     fn down_mut(&mut self, idx: usize) -> Option<&mut Self>;
 }
+
+/// A node type that knows how many children each node has. This enables
+/// cursor methods like [`TreeCursor::down_nth`] and
+/// [`TreeCursor::is_last_child`].
+///
+/// `child_count` must agree with [`Down`] and [`DownMut`], if they're
+/// implemented: `down(idx)` and `down_mut(idx)` must return a node exactly
+/// when `idx < child_count()`.
+///
+/// [`TreeCursor::down_nth`]: cursor::TreeCursor::down_nth
+/// [`TreeCursor::is_last_child`]: cursor::TreeCursor::is_last_child
+pub trait ExactSizeDown {
+    /// Returns the number of children this node has.
+    fn child_count(&self) -> usize;
+}
+
+/// A node type whose nodes know their parents, such as an arena tree that
+/// stores parent indices. This makes it possible to start a cursor at any
+/// node with [`TreeCursor::new_at`].
+///
+/// [`TreeCursor::new_at`]: cursor::TreeCursor::new_at
+pub trait Up {
+    /// Returns this node's parent, or `None` if this node is the root.
+    fn parent(&self) -> Option<&Self>;
+
+    /// Returns the index that, when passed to [`Down::down`] on this node's
+    /// parent, yields this node. This is only called on nodes that have a
+    /// parent.
+    fn index_in_parent(&self) -> usize;
+}
@@ -0,0 +1,249 @@
+//! Hooks for watching a cursor move.
+//!
+//! [`TreeCursor`] and [`TreeCursorMut`] take an optional [`Observer`] type
+//! parameter, which is told about every change to the cursor's position and
+//! "next child" counters as a [`Move`]. The default, [`NoObserver`], does
+//! nothing and takes up no space, so cursors made with `new` cost the same
+//! as if observers didn't exist. To observe a cursor, create it with
+//! `with_observer` instead.
+//!
+//! [`Recorder`] is an observer that keeps every move, and can replay them
+//! against another cursor, which makes it easy to capture a failing
+//! traversal and step through it again on a smaller tree.
+//!
+//! Cursors made from an observed cursor, such as with [`take_cursor`] or
+//! [`as_cursor`], aren't observed themselves. Taking one and rejoining it is
+//! reported as the moves that get the original cursor from where it was to
+//! where it ends up.
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursor;
+//! use tree_cursor::observe::{Move, Recorder};
+//! use tree_cursor::prelude::*;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! let t = Node("a", vec![Node("b", vec![]), Node("c", vec![])]);
+//! let mut cur = TreeCursor::with_observer(&t, Recorder::new());
+//! assert!(cur.down());
+//! assert!(cur.up());
+//! assert!(cur.down());
+//! assert_eq!(cur.observer().moves(), [
+//!     Move::Down { depth: 1, idx: 0 },
+//!     Move::Up { depth: 0 },
+//!     Move::Down { depth: 1, idx: 1 },
+//! ]);
+//!
+//! let u = Node("x", vec![Node("y", vec![]), Node("z", vec![])]);
+//! let mut other = TreeCursor::new(&u);
+//! assert!(cur.observer().replay(&mut other));
+//! assert_eq!(other.get().0, "z");
+//! ```
+//!
+//! [`TreeCursor`]: ::cursor::TreeCursor
+//! [`TreeCursorMut`]: ::cursor::TreeCursorMut
+//! [`take_cursor`]: ::cursor::TreeCursor::take_cursor
+//! [`as_cursor`]: ::cursor::TreeCursorMut::as_cursor
+
+use alloc::vec::Vec;
+use cursor::{TreeCursor, TreeCursorMut};
+use prelude::*;
+
+/// A change to a cursor's position or "next child" counters.
+///
+/// `depth` is always the depth of the active node after the change, where
+/// the cursor's root is at depth 0.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Move {
+    /// The cursor moved down to a child of the node it was at. `idx` is that
+    /// node's "next child" counter before it was incremented.
+    Down {
+        /// See [`Move`].
+        depth: usize,
+        /// See [`Move::Down`].
+        idx: usize,
+    },
+    /// The cursor moved up one node.
+    Up {
+        /// See [`Move`].
+        depth: usize,
+    },
+    /// The active node's "next child" counter was reset to 0, either by
+    /// `zero` or by `up` at the root.
+    Zero {
+        /// See [`Move`].
+        depth: usize,
+    },
+    /// The active node's "next child" counter was set to `idx`, such as by
+    /// `down_nth` or `set_pos`.
+    Set {
+        /// See [`Move`].
+        depth: usize,
+        /// See [`Move::Set`].
+        idx: usize,
+    },
+    /// The active node was handed to a new cursor by `take_cursor`, and the
+    /// cursor moved up to its parent.
+    Take {
+        /// See [`Move`].
+        depth: usize,
+    },
+    /// A child of the active node was handed to a new cursor by
+    /// `down_take_cursor`, and the cursor stayed where it was. `idx` is the
+    /// active node's "next child" counter before it was incremented.
+    DownTake {
+        /// See [`Move`].
+        depth: usize,
+        /// See [`Move::DownTake`].
+        idx: usize,
+    },
+}
+
+impl Move {
+    /// Returns the depth of the active node after this move.
+    pub fn depth(&self) -> usize {
+        match *self {
+            Move::Down { depth, .. }
+            | Move::Up { depth }
+            | Move::Zero { depth }
+            | Move::Set { depth, .. }
+            | Move::Take { depth }
+            | Move::DownTake { depth, .. } => depth,
+        }
+    }
+}
+
+/// Receives the moves of a cursor. See the [module documentation](self).
+pub trait Observer {
+    /// Called after the cursor makes a move.
+    fn on_move(&mut self, m: Move);
+}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn on_move(&mut self, m: Move) {
+        (**self).on_move(m)
+    }
+}
+
+/// The default observer, which ignores every move.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct NoObserver;
+
+impl Observer for NoObserver {
+    #[inline(always)]
+    fn on_move(&mut self, _m: Move) { }
+}
+
+/// An observer that records every move, so it can be inspected or replayed.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Recorder {
+    moves: Vec<Move>,
+}
+
+impl Observer for Recorder {
+    fn on_move(&mut self, m: Move) {
+        self.moves.push(m);
+    }
+}
+
+impl Recorder {
+    /// Creates a `Recorder` with no moves.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded moves, oldest first.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Forgets every recorded move.
+    pub fn clear(&mut self) {
+        self.moves.clear();
+    }
+
+    /// Makes the recorded moves with `cur`, which should start at the same
+    /// position and with the same counters as the recorded cursor did.
+    ///
+    /// Moves down are made with [`Down::down`] and the recorded counter, so
+    /// moves made with a [`down_map`] closure that picks some other child
+    /// are replayed as if they had been made with [`down`].
+    /// Returns false, leaving `cur` after the last move that matched, as
+    /// soon as `cur` can't make a move or ends up at a different depth than
+    /// the recorded cursor did.
+    ///
+    /// [`down_map`]: TreeCursor::down_map
+    /// [`down`]: TreeCursor::down
+    pub fn replay<'n, 'f, N, O>(&self, cur: &mut TreeCursor<'n, 'f, N, O>)
+        -> bool
+    where
+        N: 'n + Down,
+        O: Observer,
+    {
+        for &m in &self.moves {
+            let ok = match m {
+                Move::Down { idx, .. } => cur.down_map(|n, _| n.down(idx)),
+                Move::Up { .. } => cur.up_n(1),
+                Move::Zero { .. } => {
+                    cur.zero();
+                    true
+                },
+                Move::Set { idx, .. } => {
+                    cur.set_counter(idx);
+                    true
+                },
+                Move::Take { .. } => cur.take_cursor().is_some(),
+                Move::DownTake { idx, .. } => {
+                    cur.down_map_take_cursor(|n, _| n.down(idx)).is_some()
+                },
+            };
+            if !ok || cur.depth() != m.depth() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Like [`replay`], but for a [`TreeCursorMut`].
+    ///
+    /// [`replay`]: Recorder::replay
+    pub fn replay_mut<'n, 'f, N, O>(
+        &self,
+        cur: &mut TreeCursorMut<'n, 'f, N, O>,
+    ) -> bool
+    where
+        N: 'n + DownMut,
+        O: Observer,
+    {
+        for &m in &self.moves {
+            let ok = match m {
+                Move::Down { idx, .. } => {
+                    cur.down_map(|n, _| n.down_mut(idx))
+                },
+                Move::Up { .. } => cur.up_n(1),
+                Move::Zero { .. } => {
+                    cur.zero();
+                    true
+                },
+                Move::Set { idx, .. } => {
+                    cur.set_counter(idx);
+                    true
+                },
+                Move::Take { .. } => cur.take_cursor().is_some(),
+                Move::DownTake { idx, .. } => {
+                    cur.down_map_take_cursor(|n, _| n.down_mut(idx)).is_some()
+                },
+            };
+            if !ok || cur.depth() != m.depth() {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,244 @@
+//! A shared cursor that can be forked in constant time.
+//!
+//! [`PersistentTreeCursor`] has the same navigation API as [`TreeCursor`],
+//! but its ancestors are kept in a shared, immutable linked list instead of a
+//! stack. Cloning one is O(1) no matter how deep it is, and a clone shares
+//! every ancestor with the original until one of them moves up past the
+//! point where they diverged. This makes it a good fit for backtracking
+//! searches that fork the cursor at each branch point.
+//!
+//! ```
+//! use tree_cursor::persistent::PersistentTreeCursor;
+//! use tree_cursor::prelude::*;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! let t = Node("foo", vec![
+//!     Node("bar", vec![Node("baz", vec![])]),
+//!     Node("zup", vec![]),
+//! ]);
+//! let mut cur = PersistentTreeCursor::new(&t);
+//! assert!(cur.down());
+//! let mut fork = cur.clone();
+//! assert!(fork.down());
+//! assert_eq!(fork.get().0, "baz");
+//! assert!(cur.up());
+//! assert!(cur.down());
+//! assert_eq!(cur.get().0, "zup");
+//! assert!(fork.up());
+//! assert_eq!(fork.get().0, "bar");
+//! ```
+//!
+//! [`TreeCursor`]: ::cursor::TreeCursor
+
+use alloc::rc::Rc;
+use core::marker::PhantomData;
+use prelude::*;
+
+// An ancestor of the active node. `idx` is its "next child" counter, which
+// can't change while the frame is shared; it's only ever read back by `up`.
+#[derive(Debug)]
+struct Frame<'n, N: 'n> {
+    node: &'n N,
+    idx: usize,
+    parent: Option<Rc<Frame<'n, N>>>,
+}
+
+// Dropping a long, unshared chain recursively could overflow the stack, so
+// unlink it one frame at a time.
+impl<'n, N: 'n> Drop for Frame<'n, N> {
+    fn drop(&mut self) {
+        let mut next = self.parent.take();
+        while let Some(rc) = next {
+            match Rc::try_unwrap(rc) {
+                Ok(mut frame) => next = frame.parent.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// A cursor that holds a shared reference to its tree and shares its
+/// ancestors with its clones. See the [module documentation](self).
+#[derive(Debug)]
+pub struct PersistentTreeCursor<'n: 'f, 'f, N: 'n> {
+    frozen: PhantomData<&'f ()>,
+    node: &'n N,
+    idx: usize,
+    parent: Option<Rc<Frame<'n, N>>>,
+}
+
+impl<'n: 'f, 'f, N: 'n> Clone for PersistentTreeCursor<'n, 'f, N> {
+    fn clone(&self) -> Self {
+        Self {
+            frozen: PhantomData,
+            node: self.node,
+            idx: self.idx,
+            parent: self.parent.clone(),
+        }
+    }
+}
+
+impl<'n, N: 'n> PersistentTreeCursor<'n, 'n, N> {
+    /// Creates a new `PersistentTreeCursor` starting at `root`.
+    pub fn new(root: &'n N) -> Self {
+        Self::at(root)
+    }
+
+    /// Moves the cursor to the root of another tree (or to a different node
+    /// in the same tree, which then becomes the root), as if `self` had just
+    /// been created by [`new`].
+    ///
+    /// [`new`]: PersistentTreeCursor::new
+    pub fn reset(&mut self, root: &'n N) {
+        *self = Self::at(root);
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n> PersistentTreeCursor<'n, 'f, N> {
+    fn at(node: &'n N) -> Self {
+        Self {
+            frozen: PhantomData,
+            node,
+            idx: 0,
+            parent: None,
+        }
+    }
+
+    fn push(&mut self, new: &'n N) {
+        let parent = self.parent.take();
+        self.parent = Some(Rc::new(Frame {
+            node: self.node,
+            idx: self.idx,
+            parent,
+        }));
+        self.node = new;
+        self.idx = 0;
+    }
+
+    /// Like [`TreeCursor::down_map`].
+    ///
+    /// [`TreeCursor::down_map`]: ::cursor::TreeCursor::down_map
+    pub fn down_map<F>(&mut self, f: F) -> bool
+    where
+        F: Fn(&'n N, usize) -> Option<&'n N>,
+    {
+        match f(self.node, self.idx) {
+            Some(new) => {
+                self.idx += 1;
+                self.push(new);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Like [`TreeCursor::down_map_take_cursor`].
+    ///
+    /// [`TreeCursor::down_map_take_cursor`]:
+    /// ::cursor::TreeCursor::down_map_take_cursor
+    pub fn down_map_take_cursor<'s, F>(&'s mut self, f: F)
+        -> Option<PersistentTreeCursor<'n, 's, N>>
+    where
+        F: Fn(&'n N, usize) -> Option<&'n N>,
+    {
+        let new = f(self.node, self.idx)?;
+        self.idx += 1;
+        Some(PersistentTreeCursor::at(new))
+    }
+
+    /// Resets the active node's "next child" counter to 0.
+    pub fn zero(&mut self) {
+        self.idx = 0;
+    }
+
+    /// Like [`TreeCursor::up`].
+    ///
+    /// [`TreeCursor::up`]: ::cursor::TreeCursor::up
+    pub fn up(&mut self) -> bool {
+        match self.parent.take() {
+            Some(frame) => {
+                self.node = frame.node;
+                self.idx = frame.idx;
+                self.parent = frame.parent.clone();
+                true
+            },
+            None => {
+                self.zero();
+                false
+            },
+        }
+    }
+
+    /// Like [`TreeCursor::take_cursor`].
+    ///
+    /// [`TreeCursor::take_cursor`]: ::cursor::TreeCursor::take_cursor
+    pub fn take_cursor<'s>(&'s mut self)
+        -> Option<PersistentTreeCursor<'n, 's, N>>
+    {
+        let frame = self.parent.take()?;
+        let taken = PersistentTreeCursor {
+            frozen: PhantomData,
+            node: self.node,
+            idx: self.idx,
+            parent: None,
+        };
+        self.node = frame.node;
+        self.idx = frame.idx;
+        self.parent = frame.parent.clone();
+        Some(taken)
+    }
+
+    /// Returns a shared reference to the active node.
+    pub fn get(&self) -> &N {
+        self.node
+    }
+}
+
+impl<'n: 'f, 'f, N: 'n + Down> PersistentTreeCursor<'n, 'f, N> {
+    /// Like [`TreeCursor::down`].
+    ///
+    /// [`TreeCursor::down`]: ::cursor::TreeCursor::down
+    pub fn down(&mut self) -> bool {
+        self.down_map(|n, idx| n.down(idx))
+    }
+
+    /// Like [`TreeCursor::down_take_cursor`].
+    ///
+    /// [`TreeCursor::down_take_cursor`]: ::cursor::TreeCursor::down_take_cursor
+    pub fn down_take_cursor<'s>(&'s mut self)
+        -> Option<PersistentTreeCursor<'n, 's, N>>
+    {
+        self.down_map_take_cursor(|n, idx| n.down(idx))
+    }
+}
+
+impl<'n: 'f, 'f, N> PersistentTreeCursor<'n, 'f, N>
+where
+    N: 'n + Down + ExactSizeDown,
+{
+    /// Like [`TreeCursor::down_rev`].
+    ///
+    /// [`TreeCursor::down_rev`]: ::cursor::TreeCursor::down_rev
+    pub fn down_rev(&mut self) -> bool {
+        let end = match self.idx {
+            0 => self.node.child_count(),
+            idx => idx - 1,
+        };
+        if end == 0 {
+            return false;
+        }
+        let old_idx = self.idx;
+        self.idx = end - 1;
+        self.down() || {
+            self.idx = old_idx;
+            false
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Selections spanning a run of nodes in document order.
+//!
+//! A [`PosRange`] is delimited by two [`TreeCursorPos`]s, which can be at
+//! different depths. It contains every node from the first one to the last
+//! one in document order (the order a depth-first traversal enters them in),
+//! including both ends.
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursorMut;
+//! use tree_cursor::prelude::*;
+//! use tree_cursor::range::PosRange;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! impl DownMut for Node {
+//!     fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+//!         self.1.get_mut(idx)
+//!     }
+//! }
+//!
+//! let mut t = Node("a", vec![
+//!     Node("b", vec![Node("c", vec![]), Node("d", vec![])]),
+//!     Node("e", vec![Node("f", vec![])]),
+//! ]);
+//! let range = {
+//!     let mut cur = TreeCursorMut::new(&mut t);
+//!     assert!(cur.down());
+//!     assert!(cur.down());
+//!     assert!(cur.up());
+//!     assert!(cur.down());
+//!     let d = cur.pos();
+//!     assert!(cur.up());
+//!     assert!(cur.up());
+//!     assert!(cur.down());
+//!     assert!(cur.down());
+//!     PosRange::new(cur.pos(), d)
+//! };
+//! let names: Vec<_> = range.nodes(&t).map(|n| n.0).collect();
+//! assert_eq!(names, ["d", "e", "f"]);
+//!
+//! let cover = range.covering_subtrees(&t);
+//! let mut cur = TreeCursorMut::new(&mut t);
+//! let cover: Vec<_> = cover.iter().map(|p| {
+//!     cur.set_pos(p);
+//!     cur.get().0
+//! }).collect();
+//! assert_eq!(cover, ["d", "e"]);
+//! ```
+//!
+//! [`TreeCursorPos`]: ::cursor::TreeCursorPos
+
+use alloc::vec::Vec;
+use cursor::{TreeCursor, TreeCursorPos};
+use observe::{NoObserver, Observer};
+use prelude::*;
+
+/// The nodes between two positions, inclusive, in document order. See the
+/// [module documentation](self).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PosRange {
+    start: TreeCursorPos,
+    end: TreeCursorPos,
+}
+
+impl PosRange {
+    /// Creates a range between the nodes at `a` and `b`, which can be given
+    /// in either order.
+    pub fn new(a: TreeCursorPos, b: TreeCursorPos) -> Self {
+        if b < a {
+            Self { start: b, end: a }
+        } else {
+            Self { start: a, end: b }
+        }
+    }
+
+    /// Returns the position of the first node in the range.
+    pub fn start(&self) -> &TreeCursorPos {
+        &self.start
+    }
+
+    /// Returns the position of the last node in the range.
+    pub fn end(&self) -> &TreeCursorPos {
+        &self.end
+    }
+
+    /// Returns true if the node at `pos` is in the range. "Next child"
+    /// counters of the nodes at `pos` and at the ends of the range are
+    /// ignored.
+    pub fn contains(&self, pos: &TreeCursorPos) -> bool {
+        let path = pos.path();
+        self.start.path() <= path && path <= self.end.path()
+    }
+
+    /// Returns the positions of the fewest whole subtrees that lie inside
+    /// the range and together contain its nodes, in document order, in the
+    /// tree rooted at `root`.
+    ///
+    /// Each subtree's root is in the range, and so is every node under it,
+    /// so no subtree reaches past either end. The one exception is a node
+    /// above the end of the range with children after it: no whole subtree
+    /// inside the range contains such a node, so none is returned for it,
+    /// though its children are still covered. Use [`nodes`] to visit those
+    /// as well.
+    ///
+    /// The positions have their "next child" counters reset. The result is
+    /// empty if there's no node at the start of the range.
+    ///
+    /// [`nodes`]: PosRange::nodes
+    pub fn covering_subtrees<N: Down>(&self, root: &N) -> Vec<TreeCursorPos> {
+        let mut subtrees = Vec::new();
+        let mut cur = TreeCursor::new(root);
+        if !cur.follow_path(self.start.path()) {
+            return subtrees;
+        }
+        let end = self.end.path();
+        loop {
+            let path = cur.counter_path();
+            if &path[..] > end {
+                break;
+            }
+            let moved = if ends_by(cur.get(), &path, end) {
+                let mut v = path;
+                v.push(0);
+                subtrees.push(TreeCursorPos(v));
+                cur.skip_subtree()
+            } else {
+                cur.down()
+            };
+            if !moved {
+                break;
+            }
+        }
+        subtrees
+    }
+
+    /// Returns an iterator over the nodes in the range, in document order,
+    /// in the tree rooted at `root`. If there's no node at the end of the
+    /// range, the iterator runs to the end of the tree. If there's no node at
+    /// the start of the range, the iterator is empty.
+    pub fn nodes<'n, N: 'n + Down>(
+        &self,
+        root: &'n N,
+    ) -> RangeNodes<'n, 'n, N> {
+        self.nodes_with(TreeCursor::new(root))
+    }
+
+    /// Like [`nodes`], except that the iterator moves `cur` through the
+    /// range, so that `cur`'s observer sees the traversal. `cur` is moved to
+    /// its root first, and the root's "next child" counter is reset. The
+    /// range's positions are relative to that root.
+    ///
+    /// [`nodes`]: PosRange::nodes
+    pub fn nodes_with<'n: 'f, 'f, N, O>(
+        &self,
+        mut cur: TreeCursor<'n, 'f, N, O>,
+    ) -> RangeNodes<'n, 'f, N, O>
+    where
+        N: 'n + Down,
+        O: Observer,
+    {
+        cur.to_root();
+        cur.zero();
+        let found = cur.follow_path(self.start.path());
+        RangeNodes {
+            cur: if found { Some(cur) } else { None },
+            end: self.end.path().to_vec(),
+        }
+    }
+}
+
+// Returns true if no node in the subtree of `node`, whose ancestors'
+// counters are `path`, comes after the node at `end` in document order.
+// `path` mustn't come after `end`.
+fn ends_by<N: Down>(mut node: &N, path: &[usize], end: &[usize]) -> bool {
+    if !end.starts_with(path) {
+        return true;
+    }
+    for &idx in &end[path.len()..] {
+        // `idx` is the child's index plus one, so it's the next sibling's.
+        if node.down(idx).is_some() {
+            return false;
+        }
+        match idx.checked_sub(1).and_then(|i| node.down(i)) {
+            Some(child) => node = child,
+            None => return true,
+        }
+    }
+    node.down(0).is_none()
+}
+
+/// An iterator over the nodes in a range, returned by [`PosRange::nodes`]
+/// and [`PosRange::nodes_with`].
+#[derive(Debug)]
+pub struct RangeNodes<'n: 'f, 'f, N: 'n, O = NoObserver> {
+    // At the next node to return, or `None` once the iterator is done.
+    cur: Option<TreeCursor<'n, 'f, N, O>>,
+    end: Vec<usize>,
+}
+
+impl<'n: 'f, 'f, N, O> Iterator for RangeNodes<'n, 'f, N, O>
+where
+    N: 'n + Down,
+    O: Observer,
+{
+    type Item = &'n N;
+
+    fn next(&mut self) -> Option<&'n N> {
+        let (node, done) = {
+            let cur = self.cur.as_mut()?;
+            let node = cur.get_ref();
+            (node, cur.is_at(&self.end) || !cur.next_preorder())
+        };
+        if done {
+            self.cur = None;
+        }
+        Some(node)
+    }
+}
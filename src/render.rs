@@ -0,0 +1,203 @@
+//! Box-drawing rendering of trees, mostly for debugging.
+//!
+//! ```
+//! use tree_cursor::prelude::*;
+//! use tree_cursor::render::Renderer;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! let t = Node("foo", vec![
+//!     Node("bar", vec![Node("baz", vec![])]),
+//!     Node("zup", vec![]),
+//! ]);
+//! let s = Renderer::new(|n: &Node| n.0.to_string()).render(&t);
+//! assert_eq!(s, "\
+//! foo
+//! ├── bar
+//! │   └── baz
+//! └── zup
+//! ");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use cursor::TreeCursor;
+use observe::Observer;
+use prelude::*;
+
+/// The set of characters used to draw a tree.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Charset {
+    /// Unicode box-drawing characters (`├──`, `└──`, `│`).
+    Unicode,
+    /// Plain ASCII (`|--`, `` `-- ``, `|`).
+    Ascii,
+}
+
+impl Charset {
+    fn branch(self) -> &'static str {
+        match self {
+            Charset::Unicode => "├── ",
+            Charset::Ascii => "|-- ",
+        }
+    }
+
+    fn last_branch(self) -> &'static str {
+        match self {
+            Charset::Unicode => "└── ",
+            Charset::Ascii => "`-- ",
+        }
+    }
+
+    fn vertical(self) -> &'static str {
+        match self {
+            Charset::Unicode => "│   ",
+            Charset::Ascii => "|   ",
+        }
+    }
+
+    fn elided(self) -> &'static str {
+        match self {
+            Charset::Unicode => " …",
+            Charset::Ascii => " ...",
+        }
+    }
+
+    fn marker(self) -> &'static str {
+        match self {
+            Charset::Unicode => " ◀",
+            Charset::Ascii => " <",
+        }
+    }
+}
+
+/// Renders a tree as indented lines joined by box-drawing characters.
+///
+/// Nodes whose children aren't shown, either because they're at the maximum
+/// depth or because they're collapsed, are suffixed with an ellipsis if they
+/// have any children.
+pub struct Renderer<'a, N: 'a> {
+    label: Box<dyn Fn(&N) -> String + 'a>,
+    max_depth: Option<usize>,
+    collapse: Box<dyn Fn(&N) -> bool + 'a>,
+    highlight: Option<Vec<*const N>>,
+    charset: Charset,
+}
+
+impl<'a, N: 'a + Down> Renderer<'a, N> {
+    /// Creates a new `Renderer` that uses `label` to produce the text for each
+    /// node. `label` shouldn't return text containing newlines.
+    pub fn new<F>(label: F) -> Self
+    where
+        F: Fn(&N) -> String + 'a,
+    {
+        Self {
+            label: Box::new(label),
+            max_depth: None,
+            collapse: Box::new(|_| false),
+            highlight: None,
+            charset: Charset::Unicode,
+        }
+    }
+
+    /// Stops rendering below depth `depth`. The root is at depth 0.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Hides the children of every node for which `f` returns true.
+    pub fn collapse<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&N) -> bool + 'a,
+    {
+        self.collapse = Box::new(f);
+        self
+    }
+
+    /// Marks the active node of `cur`. `cur` must have been created at the
+    /// root of the tree that's rendered; otherwise nothing is marked.
+    pub fn highlight<'n: 'f, 'f, O: Observer>(
+        mut self,
+        cur: &TreeCursor<'n, 'f, N, O>,
+    ) -> Self {
+        self.highlight = Some(cur.ptr_path());
+        self
+    }
+
+    /// Sets the characters used to draw the tree. The default is
+    /// [`Charset::Unicode`].
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Renders the tree rooted at `root` into a new `String`.
+    pub fn render(&self, root: &N) -> String {
+        let mut s = String::new();
+        self.write(root, &mut s).unwrap();
+        s
+    }
+
+    /// Renders the tree rooted at `root` into `w`.
+    pub fn write<W: Write>(&self, root: &N, w: &mut W) -> fmt::Result {
+        let mut c = TreeCursor::new(root);
+        let mut prefix = String::new();
+        let mut prefix_lens = Vec::new();
+        let mut expand = self.expands(&c);
+        self.write_line(&c, "", expand, w)?;
+        loop {
+            if expand && c.down() {
+                let last = !c.has_next_sibling();
+                prefix_lens.push(prefix.len());
+                let lead = if last {
+                    self.charset.last_branch()
+                } else {
+                    self.charset.branch()
+                };
+                expand = self.expands(&c);
+                self.write_line(&c, &(prefix.clone() + lead), expand, w)?;
+                prefix.push_str(if last { "    " } else {
+                    self.charset.vertical()
+                });
+            } else if c.up() {
+                prefix.truncate(prefix_lens.pop().unwrap());
+                expand = true;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn expands(&self, c: &TreeCursor<N>) -> bool {
+        !matches!(self.max_depth, Some(d) if c.depth() >= d)
+            && !(self.collapse)(c.get())
+    }
+
+    fn write_line<W: Write>(
+        &self,
+        c: &TreeCursor<N>,
+        lead: &str,
+        expanded: bool,
+        w: &mut W,
+    ) -> fmt::Result {
+        w.write_str(lead)?;
+        w.write_str(&(self.label)(c.get()))?;
+        if !expanded && c.get().down(0).is_some() {
+            w.write_str(self.charset.elided())?;
+        }
+        if self.highlight.as_ref().is_some_and(|h| c.path_eq(h)) {
+            w.write_str(self.charset.marker())?;
+        }
+        w.write_char('\n')
+    }
+}
@@ -0,0 +1,131 @@
+//! Serialization of [`Down`] trees with [serde]. Requires the `serde`
+//! feature.
+//!
+//! Two representations are available:
+//! - [`Nested`] writes each node as a struct with a `label` field and a
+//!   `children` sequence. Siblings are visited with a cursor, but because
+//!   serde's data model nests compound values by calling into the serializer,
+//!   each level of the tree still takes a stack frame inside the serializer.
+//! - [`Flat`] writes the tree as one sequence of `(depth, label)` pairs in
+//!   pre-order. It's produced by a single cursor traversal with no recursion
+//!   at all, so it's the one to use for trees that may be arbitrarily deep.
+//!
+//! [`Down`]: ::Down
+//! [serde]: https://serde.rs/
+
+use cursor::TreeCursor;
+use prelude::*;
+use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+/// Serializes the tree rooted at a node as nested structs. See the [module
+/// documentation](self).
+///
+/// Each level of the tree is serialized by a nested call into the
+/// serializer, so unlike [`Flat`], this uses stack space proportional to the
+/// depth of the tree and can overflow the stack on very deep trees. Use
+/// [`Flat`] for trees whose depth isn't bounded.
+pub struct Nested<'a, N: 'a, F> {
+    root: &'a N,
+    label: F,
+}
+
+impl<'a, N: 'a, F> Nested<'a, N, F> {
+    /// Creates a new `Nested` for the tree rooted at `root`, using `label` to
+    /// produce each node's label.
+    pub fn new(root: &'a N, label: F) -> Self {
+        Self { root, label }
+    }
+}
+
+impl<'a, N, F, L> Serialize for Nested<'a, N, F>
+where
+    N: 'a + Down,
+    F: Fn(&N) -> L,
+    L: Serialize,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        Subtree { node: self.root, label: &self.label }.serialize(s)
+    }
+}
+
+struct Subtree<'a, N: 'a, F: 'a> {
+    node: &'a N,
+    label: &'a F,
+}
+
+impl<'a, N, F, L> Serialize for Subtree<'a, N, F>
+where
+    N: 'a + Down,
+    F: Fn(&N) -> L,
+    L: Serialize,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut st = s.serialize_struct("Node", 2)?;
+        st.serialize_field("label", &(self.label)(self.node))?;
+        st.serialize_field("children", &Children {
+            node: self.node,
+            label: self.label,
+        })?;
+        st.end()
+    }
+}
+
+struct Children<'a, N: 'a, F: 'a> {
+    node: &'a N,
+    label: &'a F,
+}
+
+impl<'a, N, F, L> Serialize for Children<'a, N, F>
+where
+    N: 'a + Down,
+    F: Fn(&N) -> L,
+    L: Serialize,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(None)?;
+        let mut c = TreeCursor::new(self.node);
+        while let Some(child) = c.down_take_cursor() {
+            seq.serialize_element(&Subtree {
+                node: child.get(),
+                label: self.label,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes the tree rooted at a node as a flat pre-order sequence of
+/// `(depth, label)` pairs. See the [module documentation](self).
+pub struct Flat<'a, N: 'a, F> {
+    root: &'a N,
+    label: F,
+}
+
+impl<'a, N: 'a, F> Flat<'a, N, F> {
+    /// Creates a new `Flat` for the tree rooted at `root`, using `label` to
+    /// produce each node's label.
+    pub fn new(root: &'a N, label: F) -> Self {
+        Self { root, label }
+    }
+}
+
+impl<'a, N, F, L> Serialize for Flat<'a, N, F>
+where
+    N: 'a + Down,
+    F: Fn(&N) -> L,
+    L: Serialize,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(None)?;
+        let mut c = TreeCursor::new(self.root);
+        'outer: loop {
+            seq.serialize_element(&(c.depth(), (self.label)(c.get())))?;
+            while !c.down() {
+                if !c.up() {
+                    break 'outer;
+                }
+            }
+        }
+        seq.end()
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ptr::NonNull;
+
+/// A node pointer and that node's "next child" counter.
+pub(crate) type Entry<N> = (NonNull<N>, usize);
+
+/// How many entries fit in a `Stack` before it spills onto the heap.
+pub(crate) const INLINE_DEPTH: usize = 16;
+
+/// The stack of a cursor. It's never empty.
+///
+/// Shallow stacks are stored inline, so creating a cursor doesn't allocate
+/// until it goes deeper than `INLINE_DEPTH`. Once a stack has spilled, it
+/// stays on the heap, so that [`reset`] can reuse the allocation.
+///
+/// [`reset`]: Stack::reset
+// Keeping shallow stacks inline is the whole point, so the size difference
+// between variants is deliberate.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Stack<N> {
+    Inline { len: usize, buf: [Entry<N>; INLINE_DEPTH] },
+    Heap(Vec<Entry<N>>),
+}
+
+impl<N> Stack<N> {
+    pub(crate) fn new(first: Entry<N>) -> Self {
+        Stack::Inline { len: 1, buf: [first; INLINE_DEPTH] }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[Entry<N>] {
+        match *self {
+            Stack::Inline { len, ref buf } => &buf[..len],
+            Stack::Heap(ref v) => v,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    pub(crate) fn last(&self) -> &Entry<N> {
+        self.as_slice().last().unwrap()
+    }
+
+    pub(crate) fn last_mut(&mut self) -> &mut Entry<N> {
+        match *self {
+            Stack::Inline { len, ref mut buf } => &mut buf[len - 1],
+            Stack::Heap(ref mut v) => v.last_mut().unwrap(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, e: Entry<N>) {
+        match *self {
+            Stack::Inline { ref mut len, ref mut buf }
+                if *len < INLINE_DEPTH =>
+            {
+                buf[*len] = e;
+                *len += 1;
+            },
+            // The buffer is full, so spill onto the heap.
+            Stack::Inline { ref buf, .. } => {
+                let mut v = Vec::with_capacity(2 * INLINE_DEPTH);
+                v.extend_from_slice(buf);
+                v.push(e);
+                *self = Stack::Heap(v);
+            },
+            Stack::Heap(ref mut v) => v.push(e),
+        }
+    }
+
+    /// Removes and returns the top entry, unless it's the only one.
+    pub(crate) fn pop(&mut self) -> Option<Entry<N>> {
+        if self.len() == 1 {
+            return None;
+        }
+        let top = *self.last();
+        self.truncate(self.len() - 1);
+        Some(top)
+    }
+
+    /// Shortens the stack to `new_len` entries, which must be at least 1.
+    pub(crate) fn truncate(&mut self, new_len: usize) {
+        debug_assert!(new_len >= 1);
+        match *self {
+            Stack::Inline { ref mut len, .. } => {
+                if new_len < *len {
+                    *len = new_len;
+                }
+            },
+            Stack::Heap(ref mut v) => v.truncate(new_len),
+        }
+    }
+
+    /// Replaces every entry with `first`, keeping any heap allocation.
+    pub(crate) fn reset(&mut self, first: Entry<N>) {
+        match *self {
+            Stack::Heap(ref mut v) => {
+                v.clear();
+                v.push(first);
+            },
+            _ => *self = Stack::new(first),
+        }
+    }
+}
+
+impl<N> Clone for Stack<N> {
+    fn clone(&self) -> Self {
+        match *self {
+            Stack::Inline { len, buf } => Stack::Inline { len, buf },
+            Stack::Heap(ref v) => Stack::Heap(v.clone()),
+        }
+    }
+}
+
+impl<N> fmt::Debug for Stack<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<N> PartialEq for Stack<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<N> Eq for Stack<N> { }
+
+impl<N> Hash for Stack<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
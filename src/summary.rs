@@ -0,0 +1,245 @@
+//! Keeping cached per-subtree summaries up to date as a tree is edited.
+//!
+//! Nodes that cache an aggregate of their subtree (a size, a sum, a hash)
+//! implement [`Summarize`]. [`SummaryCursor`] wraps a [`TreeCursorMut`] and
+//! remembers which nodes on its path are stale: getting mutable access to the
+//! active node marks it and all of its ancestors dirty, and each dirty node's
+//! summary is recomputed when the cursor moves up from it. Nodes off the path
+//! can't change without the cursor visiting them, so they're never stale.
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursorMut;
+//! use tree_cursor::prelude::*;
+//! use tree_cursor::summary::{Summarize, SummaryCursor};
+//!
+//! struct Node {
+//!     value: u32,
+//!     total: u32,
+//!     children: Vec<Node>,
+//! }
+//!
+//! impl DownMut for Node {
+//!     fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+//!         self.children.get_mut(idx)
+//!     }
+//! }
+//!
+//! impl Summarize for Node {
+//!     fn summarize(&mut self) {
+//!         self.total = self.value
+//!             + self.children.iter().map(|c| c.total).sum::<u32>();
+//!     }
+//! }
+//!
+//! fn leaf(value: u32) -> Node {
+//!     Node { value, total: value, children: vec![] }
+//! }
+//!
+//! let mut t = Node { value: 1, total: 6, children: vec![leaf(2), leaf(3)] };
+//! {
+//!     let mut cur = SummaryCursor::new(TreeCursorMut::new(&mut t));
+//!     assert!(cur.down());
+//!     cur.get_mut().value = 10;
+//!     assert!(cur.is_dirty());
+//!     assert!(cur.up());
+//!     assert_eq!(cur.get().total, 6);
+//!     assert!(cur.flush());
+//!     assert_eq!(cur.get().total, 14);
+//! }
+//! assert_eq!(t.total, 14);
+//! ```
+//!
+//! [`TreeCursorMut`]: ::cursor::TreeCursorMut
+
+use cursor::{TreeCursorMut, TreeCursorPos};
+use observe::{NoObserver, Observer};
+use prelude::*;
+
+/// A node that caches a summary of its subtree.
+pub trait Summarize {
+    /// Recomputes this node's cached summary from its own data and its
+    /// children's cached summaries, which are up to date when this is called.
+    fn summarize(&mut self);
+}
+
+/// Wraps a [`TreeCursorMut`] and recomputes stale [`Summarize`] summaries
+/// along its path. See the [module documentation](self).
+///
+/// The wrapped cursor's root is treated as the root of the tree: if it was
+/// made with [`take_cursor`] or [`down_take_cursor`], the parent cursor's
+/// nodes aren't marked dirty.
+///
+/// Moving up from a node brings its summary up to date first, so when the
+/// cursor reaches a dirty ancestor, all of the ancestor's children are up to
+/// date. Dropping a `SummaryCursor` doesn't recompute anything; call
+/// [`flush`] or [`into_inner`] first.
+///
+/// [`TreeCursorMut`]: ::cursor::TreeCursorMut
+/// [`take_cursor`]: ::cursor::TreeCursorMut::take_cursor
+/// [`down_take_cursor`]: ::cursor::TreeCursorMut::down_take_cursor
+/// [`flush`]: SummaryCursor::flush
+/// [`into_inner`]: SummaryCursor::into_inner
+#[derive(Debug)]
+pub struct SummaryCursor<'n: 'f, 'f, N: 'n, O = NoObserver> {
+    cur: TreeCursorMut<'n, 'f, N, O>,
+    depth: usize,
+    // The number of stale nodes on the path, starting from the root. Since a
+    // node's ancestors are stale whenever it is, that's all of them.
+    dirty: usize,
+}
+
+impl<'n: 'f, 'f, N, O: Observer> SummaryCursor<'n, 'f, N, O>
+where
+    N: 'n + DownMut + Summarize,
+{
+    /// Wraps `cur`. Every summary in the tree is assumed to be up to date.
+    pub fn new(cur: TreeCursorMut<'n, 'f, N, O>) -> Self {
+        let depth = cur.pos().depth();
+        Self {
+            cur,
+            depth,
+            dirty: 0,
+        }
+    }
+
+    /// Like [`TreeCursorMut::down`].
+    ///
+    /// [`TreeCursorMut::down`]: ::cursor::TreeCursorMut::down
+    pub fn down(&mut self) -> bool {
+        if self.cur.down() {
+            self.depth += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the current position of the cursor, like
+    /// [`TreeCursorMut::pos`].
+    ///
+    /// [`TreeCursorMut::pos`]: ::cursor::TreeCursorMut::pos
+    pub fn pos(&self) -> TreeCursorPos {
+        self.cur.pos()
+    }
+
+    /// Brings every summary up to date without moving the cursor, and
+    /// returns true. This recomputes each dirty node on the path, from the
+    /// active node up.
+    ///
+    /// Recomputing an ancestor means moving up to it, so the cursor then
+    /// moves back down like [`TreeCursorMut::set_pos`]. That can't find the
+    /// way back if the cursor moved down with [`down_map`] on the way from
+    /// the root (see [`TreeCursorMut::pos_is_exact`]). If there's anything
+    /// to recompute in that case, this method returns false without
+    /// recomputing or moving anything; move up past those nodes first.
+    ///
+    /// [`TreeCursorMut::set_pos`]: ::cursor::TreeCursorMut::set_pos
+    /// [`TreeCursorMut::pos_is_exact`]: ::cursor::TreeCursorMut::pos_is_exact
+    /// [`down_map`]: SummaryCursor::down_map
+    pub fn flush(&mut self) -> bool {
+        if self.dirty == 0 {
+            return true;
+        }
+        if !self.cur.pos_is_exact() {
+            return false;
+        }
+        let pos = self.cur.pos();
+        let depth = self.depth;
+        while self.up() { }
+        self.cur.set_pos(&pos);
+        self.depth = depth;
+        true
+    }
+
+    /// Brings every summary up to date with [`flush`] and unwraps the
+    /// cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `flush` returns false.
+    ///
+    /// [`flush`]: SummaryCursor::flush
+    pub fn into_inner(mut self) -> TreeCursorMut<'n, 'f, N, O> {
+        if !self.flush() {
+            panic!("can't flush summaries after down_map");
+        }
+        self.cur
+    }
+}
+
+impl<'n: 'f, 'f, N, O: Observer> SummaryCursor<'n, 'f, N, O>
+where
+    N: 'n + Summarize,
+{
+    /// Like [`TreeCursorMut::down_map`].
+    ///
+    /// [`flush`] and [`into_inner`] can't find the way back to a node the
+    /// cursor moved to this way. Move up past it before calling them.
+    ///
+    /// [`TreeCursorMut::down_map`]: ::cursor::TreeCursorMut::down_map
+    /// [`flush`]: SummaryCursor::flush
+    /// [`into_inner`]: SummaryCursor::into_inner
+    pub fn down_map<F>(&mut self, f: F) -> bool
+    where
+        F: Fn(&mut N, usize) -> Option<&mut N>,
+    {
+        if self.cur.down_map(f) {
+            self.depth += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Resets the active node's "next child" counter to 0.
+    pub fn zero(&mut self) {
+        self.cur.zero();
+    }
+
+    /// Brings the active node's summary up to date if it's dirty, then moves
+    /// up like [`TreeCursorMut::up`]. The summary is brought up to date even
+    /// at the root.
+    ///
+    /// [`TreeCursorMut::up`]: ::cursor::TreeCursorMut::up
+    pub fn up(&mut self) -> bool {
+        self.update();
+        if self.cur.up() {
+            self.depth -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the depth of the active node. The wrapped cursor's root is at
+    /// depth 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns a shared reference to the active node.
+    pub fn get(&self) -> &N {
+        self.cur.get()
+    }
+
+    /// Returns a mutable reference to the active node, and marks it and all
+    /// of its ancestors dirty.
+    pub fn get_mut(&mut self) -> &mut N {
+        self.dirty = self.depth + 1;
+        self.cur.get_mut()
+    }
+
+    /// Returns true if the active node's summary is stale.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty > self.depth
+    }
+
+    /// Brings the active node's summary up to date if it's dirty. Its
+    /// ancestors stay dirty.
+    pub fn update(&mut self) {
+        if self.is_dirty() {
+            self.cur.get_mut().summarize();
+            self.dirty = self.depth;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use cursor::TreeCursor;
+use prelude::*;
+use std::cell::OnceCell;
+use std::ptr;
+
+struct Node<'a> {
+    name: &'static str,
+    parent: Option<(usize, usize)>,
+    children: Vec<usize>,
+    arena: OnceCell<&'a [Node<'a>]>,
+}
+
+impl<'a> Node<'a> {
+    fn arena(&self) -> &'a [Node<'a>] {
+        self.arena.get().unwrap()
+    }
+}
+
+impl<'a> Down for Node<'a> {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.children.get(idx).map(|&i| &self.arena()[i])
+    }
+}
+
+impl<'a> Up for Node<'a> {
+    fn parent(&self) -> Option<&Self> {
+        self.parent.map(|(i, _)| &self.arena()[i])
+    }
+
+    fn index_in_parent(&self) -> usize {
+        self.parent.unwrap().1
+    }
+}
+
+// Each entry is a name and a parent index. Children are in arena order.
+fn arena<'a>(spec: &[(&'static str, Option<usize>)]) -> Vec<Node<'a>> {
+    let mut nodes: Vec<Node> = spec.iter().map(|&(name, _)| Node {
+        name,
+        parent: None,
+        children: vec![],
+        arena: OnceCell::new(),
+    }).collect();
+    for (i, &(_, parent)) in spec.iter().enumerate() {
+        if let Some(p) = parent {
+            let idx = nodes[p].children.len();
+            nodes[p].children.push(i);
+            nodes[i].parent = Some((p, idx));
+        }
+    }
+    nodes
+}
+
+#[test]
+fn new_at() {
+    let nodes = arena(&[
+        ("a", None),
+        ("b", Some(0)),
+        ("c", Some(1)),
+        ("d", Some(1)),
+        ("e", Some(0)),
+        ("f", Some(3)),
+    ]);
+    for n in &nodes {
+        n.arena.set(&nodes).ok().unwrap();
+    }
+
+    let mut c = TreeCursor::new_at(&nodes[5]);
+    assert_eq!(c.get().name, "f");
+    assert!(!c.down());
+    assert!(c.up());
+    assert_eq!(c.get().name, "d");
+    assert!(c.up());
+    assert_eq!(c.get().name, "b");
+    // Counters are as if we'd gone down from the root.
+    assert_eq!(c.peek_next_child().map(|n| n.name), None);
+    assert!(c.up());
+    assert_eq!(c.get().name, "a");
+    assert!(c.down());
+    assert_eq!(c.get().name, "e");
+    assert!(c.up());
+    assert!(!c.up());
+
+    let mut c = TreeCursor::new_at(&nodes[2]);
+    assert_eq!(c.to_root(), 2);
+    assert!(ptr::eq(c.get(), &nodes[0]));
+
+    let c = TreeCursor::new_at(&nodes[0]);
+    assert!(c.peek_parent().is_none());
+    assert_eq!(c.peek_next_child().unwrap().name, "b");
+}
@@ -0,0 +1,86 @@
+use build::{self, BuildError, BuildNode, Event, TreeBuilder};
+
+#[derive(Debug, PartialEq)]
+struct N(usize, Vec<N>);
+
+impl BuildNode for N {
+    type Value = usize;
+
+    fn create(value: usize) -> Self {
+        N(value, vec![])
+    }
+
+    fn append_child(&mut self, child: Self) {
+        self.1.push(child);
+    }
+}
+
+fn n(x: usize, v: Vec<N>) -> N {
+    N(x, v)
+}
+
+#[test]
+fn balanced() {
+    use self::Event::*;
+
+    let t: N = build::build(vec![
+        Enter(0),
+        Enter(1),
+        Leave,
+        Enter(2),
+        Enter(3),
+        Leave,
+        Enter(4),
+        Leave,
+        Leave,
+        Leave,
+    ]).unwrap();
+    assert_eq!(t, n(0, vec![
+        n(1, vec![]),
+        n(2, vec![
+            n(3, vec![]),
+            n(4, vec![]),
+        ]),
+    ]));
+
+    let t: N = build::build(vec![Enter(7), Leave]).unwrap();
+    assert_eq!(t, n(7, vec![]));
+}
+
+#[test]
+fn errors() {
+    use self::Event::*;
+
+    assert_eq!(build::build::<N, _>(vec![]), Err(BuildError::Empty));
+    assert_eq!(
+        build::build::<N, _>(vec![Enter(0), Leave, Leave]),
+        Err(BuildError::UnmatchedLeave(2)),
+    );
+    assert_eq!(
+        build::build::<N, _>(vec![Enter(0), Leave, Enter(1), Leave]),
+        Err(BuildError::ExtraRoot(2)),
+    );
+    assert_eq!(
+        build::build::<N, _>(vec![Enter(0), Enter(1), Leave]),
+        Err(BuildError::Unfinished { pos: 3, depth: 1 }),
+    );
+    assert_eq!(
+        BuildError::UnmatchedLeave(2).to_string(),
+        "unmatched Leave event at position 2",
+    );
+}
+
+#[test]
+fn incremental() {
+    let mut b = TreeBuilder::<N>::new();
+    assert_eq!(b.leave(), Err(BuildError::UnmatchedLeave(0)));
+    assert_eq!(b.pos(), 1);
+    b.enter(0).unwrap();
+    b.push(Event::Enter(1)).unwrap();
+    assert_eq!(b.depth(), 2);
+    b.leave().unwrap();
+    b.leave().unwrap();
+    assert_eq!(b.depth(), 0);
+    assert_eq!(b.enter(2), Err(BuildError::ExtraRoot(5)));
+    assert_eq!(b.finish(), Ok(n(0, vec![n(1, vec![])])));
+}
@@ -0,0 +1,110 @@
+use cursor::TreeCursor;
+use dot::Dot;
+use observe::{Move, Recorder};
+use prelude::*;
+
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+        ]),
+        N("d\"", vec![]),
+    ])
+}
+
+fn label(n: &N) -> String {
+    n.0.to_string()
+}
+
+#[test]
+fn structure() {
+    let t = t();
+    assert_eq!(Dot::new(label).render(&t), r#"digraph "tree" {
+    n0 [label="a"];
+    n1 [label="b"];
+    n0 -> n1;
+    n2 [label="c"];
+    n1 -> n2;
+    n3 [label="d\""];
+    n0 -> n3;
+}
+"#);
+}
+
+#[test]
+fn attrs_and_highlight() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    assert!(c.down());
+    assert!(c.down());
+    let dot = Dot::new(label)
+        .name("g")
+        .attrs(|n| if n.1.is_empty() {
+            vec![("shape".to_string(), "box".to_string())]
+        } else {
+            vec![]
+        })
+        .highlight(&c);
+    assert_eq!(dot.render(&t), r#"digraph "g" {
+    n0 [label="a", color="red", penwidth="2"];
+    n1 [label="b", color="red", penwidth="2"];
+    n0 -> n1 [color="red", penwidth="2"];
+    n2 [label="c", shape="box", color="red", penwidth="2"];
+    n1 -> n2 [color="red", penwidth="2"];
+    n3 [label="d\"", shape="box"];
+    n0 -> n3;
+}
+"#);
+}
+
+#[test]
+fn highlight_observed() {
+    let t = t();
+    let mut c = TreeCursor::with_observer(&t, Recorder::new());
+    assert!(c.down());
+    assert!(c.up());
+    assert!(c.down());
+    let s = Dot::new(label).highlight(&c).render(&t);
+    assert!(s.contains("n3 [label=\"d\\\"\", color=\"red\""));
+    assert!(s.contains("n0 -> n3 [color=\"red\""));
+    assert!(!s.contains("n1 [label=\"b\", color"));
+    assert_eq!(c.observer().moves(), [
+        Move::Down { depth: 1, idx: 0 },
+        Move::Up { depth: 0 },
+        Move::Down { depth: 1, idx: 1 },
+    ]);
+}
+
+#[test]
+fn links() {
+    let t = t();
+    let outside = N("x", vec![]);
+    let dot = Dot::new(label).links(|n, idx| match (n.0, idx) {
+        ("c", 0) => Some(&t.1[1]),
+        ("c", 1) => Some(&outside),
+        ("b", 0) => Some(&t),
+        _ => None,
+    });
+    assert_eq!(dot.render(&t), r#"digraph "tree" {
+    n0 [label="a"];
+    n1 [label="b"];
+    n0 -> n1;
+    n2 [label="c"];
+    n1 -> n2;
+    n3 [label="d\""];
+    n0 -> n3;
+    n4 [label="x"];
+    n1 -> n0 [style="dashed", constraint="false"];
+    n2 -> n3 [style="dashed", constraint="false"];
+    n2 -> n4 [style="dashed", constraint="false"];
+}
+"#);
+}
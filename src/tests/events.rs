@@ -0,0 +1,130 @@
+use cursor::TreeCursor;
+use events::{Event, Events};
+use prelude::*;
+
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+impl ExactSizeDown for N {
+    fn child_count(&self) -> usize {
+        self.1.len()
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+            N("d", vec![]),
+        ]),
+        N("e", vec![]),
+    ])
+}
+
+fn fmt(ev: Event<N>) -> String {
+    match ev {
+        Event::Enter(n, depth) => format!("+{}{}", n.0, depth),
+        Event::Leave(n, depth) => format!("-{}{}", n.0, depth),
+    }
+}
+
+#[test]
+fn full() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let evs: Vec<_> = Events::new(&mut c).map(fmt).collect();
+    assert_eq!(evs, [
+        "+a0", "+b1", "+c2", "-c2", "+d2", "-d2", "-b1", "+e1", "-e1", "-a0",
+    ]);
+    assert_eq!(c.get().0, "a");
+    assert!(!c.down());
+}
+
+#[test]
+fn resets_counter() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    assert!(c.down());
+    assert!(c.up());
+    let evs: Vec<_> = Events::new(&mut c).map(fmt).collect();
+    assert_eq!(evs, [
+        "+a0", "+b1", "+c2", "-c2", "+d2", "-d2", "-b1", "+e1", "-e1", "-a0",
+    ]);
+}
+
+#[test]
+fn subtree_of_active_node() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    assert!(c.down());
+    let evs: Vec<_> = Events::new(&mut c).map(fmt).collect();
+    assert_eq!(evs, ["+b0", "+c1", "-c1", "+d1", "-d1", "-b0"]);
+    assert_eq!(c.get().0, "b");
+    assert!(c.up());
+    assert!(c.down());
+    assert_eq!(c.get().0, "e");
+}
+
+#[test]
+fn skip() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let mut evs = Vec::new();
+    {
+        let mut it = Events::new(&mut c);
+        while let Some(ev) = it.next() {
+            match ev {
+                Event::Enter(n, _) if n.0 == "b" => it.skip_subtree(),
+                Event::Enter(n, _) if n.0 == "e" => it.skip_subtree(),
+                _ => (),
+            }
+            evs.push(fmt(ev));
+        }
+    }
+    assert_eq!(evs, ["+a0", "+b1", "-b1", "+e1", "-e1", "-a0"]);
+
+    // Skipping after a Leave skips the rest of the parent.
+    let mut evs = Vec::new();
+    {
+        let mut it = Events::new(&mut c);
+        while let Some(ev) = it.next() {
+            if let Event::Leave(n, _) = ev {
+                if n.0 == "c" {
+                    it.skip_subtree();
+                }
+            }
+            evs.push(fmt(ev));
+        }
+    }
+    assert_eq!(evs, [
+        "+a0", "+b1", "+c2", "-c2", "-b1", "+e1", "-e1", "-a0",
+    ]);
+}
+
+#[test]
+fn rev() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let evs: Vec<_> = Events::new_rev(&mut c).map(fmt).collect();
+    assert_eq!(evs, [
+        "+a0", "+e1", "-e1", "+b1", "+d2", "-d2", "+c2", "-c2", "-b1", "-a0",
+    ]);
+    assert_eq!(c.get().0, "a");
+
+    // Reverse post-order is reverse document order.
+    let mut fwd: Vec<_> = Events::new(&mut c).filter_map(|ev| match ev {
+        Event::Enter(n, _) => Some(n.0),
+        Event::Leave(..) => None,
+    }).collect();
+    fwd.reverse();
+    let rev: Vec<_> = Events::new_rev(&mut c).filter_map(|ev| match ev {
+        Event::Leave(n, _) => Some(n.0),
+        Event::Enter(..) => None,
+    }).collect();
+    assert_eq!(fwd, rev);
+}
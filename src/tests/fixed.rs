@@ -0,0 +1,112 @@
+use fixed::{CapacityError, FixedTreeCursor, FixedTreeCursorMut};
+use prelude::*;
+
+struct X {
+    n: u32,
+    v: Vec<X>,
+}
+
+impl Down for X {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.v.get(idx)
+    }
+}
+
+impl DownMut for X {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.v.get_mut(idx)
+    }
+}
+
+fn xx(n: u32, v: Vec<X>) -> X {
+    X { n, v }
+}
+
+fn x(n: u32) -> X {
+    X { n, v: vec![] }
+}
+
+fn tree() -> X {
+    xx(0, vec![
+        xx(1, vec![x(2), x(3)]),
+        x(4),
+    ])
+}
+
+#[test]
+fn capacity() {
+    let t = tree();
+    let mut c = FixedTreeCursor::<_, 1>::new(&t);
+    assert_eq!(c.down(), Ok(true));
+    assert_eq!((c.get().n, c.depth()), (1, 1));
+    // Full, but there's a child: fail without consuming it.
+    assert_eq!(c.down(), Err(CapacityError));
+    assert_eq!(c.down(), Err(CapacityError));
+    assert_eq!(c.get().n, 1);
+    assert!(c.up());
+    assert_eq!(c.down(), Ok(true));
+    assert_eq!(c.get().n, 4);
+    // Full, but there's no child: not an error.
+    assert_eq!(c.down(), Ok(false));
+    assert!(c.up());
+    assert_eq!(c.down(), Ok(false));
+    assert!(!c.up());
+    assert_eq!(c.down(), Ok(true));
+    assert_eq!(c.get().n, 1);
+}
+
+#[test]
+fn zero_depth() {
+    let t = tree();
+    let mut c = FixedTreeCursor::<_, 0>::new(&t);
+    assert_eq!(c.down(), Err(CapacityError));
+    assert_eq!(c.down_map(|_, _| None), Ok(false));
+    assert!(!c.up());
+}
+
+#[test]
+fn full_traversal() {
+    let t = tree();
+    let mut c = FixedTreeCursor::<_, 2>::new(&t);
+    let mut seen = vec![c.get().n];
+    loop {
+        while c.down().unwrap() {
+            seen.push(c.get().n);
+        }
+        if !c.up() {
+            break;
+        }
+    }
+    assert_eq!(seen, [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn mutate() {
+    let mut t = tree();
+    {
+        let mut c = FixedTreeCursorMut::<_, 1>::new(&mut t);
+        assert_eq!(c.down(), Ok(true));
+        c.get_mut().n += 10;
+        assert_eq!(c.down(), Err(CapacityError));
+        assert!(c.up());
+        assert_eq!(c.down(), Ok(true));
+        c.get_mut().n += 10;
+        assert!(c.up());
+        c.get_mut().n += 10;
+        c.zero();
+        assert_eq!(c.down(), Ok(true));
+        assert_eq!(c.get().n, 11);
+    }
+    assert_eq!((t.n, t.v[0].n, t.v[0].v[0].n, t.v[1].n), (10, 11, 2, 14));
+}
+
+#[test]
+fn reset() {
+    let t = tree();
+    let u = x(9);
+    let mut c = FixedTreeCursor::<_, 2>::new(&t);
+    assert_eq!(c.down(), Ok(true));
+    c.reset(&u);
+    assert_eq!((c.get().n, c.depth()), (9, 0));
+    assert_eq!(c.down(), Ok(false));
+}
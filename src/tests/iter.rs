@@ -0,0 +1,95 @@
+use cursor::TreeCursor;
+use prelude::*;
+
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+            N("d", vec![N("e", vec![])]),
+        ]),
+        N("f", vec![]),
+        N("g", vec![N("h", vec![])]),
+    ])
+}
+
+fn names<'n, I: IntoIterator<Item = &'n N>>(it: I) -> Vec<&'static str> {
+    it.into_iter().map(|n| n.0).collect()
+}
+
+#[test]
+fn leaves() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    assert_eq!(names(c.leaves()), ["c", "e", "f", "h"]);
+    assert_eq!(c.get().0, "a");
+    assert!(!c.down());
+    c.zero();
+    assert!(c.down());
+    assert_eq!(names(c.leaves()), ["c", "e"]);
+    assert_eq!(c.get().0, "b");
+    c.zero();
+    assert!(c.down());
+    assert_eq!(names(c.leaves()), ["c"]);
+}
+
+#[test]
+fn at_depth() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    assert_eq!(names(c.at_depth(0)), ["a"]);
+    assert_eq!(names(c.at_depth(1)), ["b", "f", "g"]);
+    assert_eq!(names(c.at_depth(2)), ["c", "d", "h"]);
+    assert_eq!(names(c.at_depth(3)), ["e"]);
+    assert!(c.at_depth(4).next().is_none());
+    assert_eq!(c.get().0, "a");
+
+    // An iterator that's dropped early leaves the cursor where it stopped.
+    assert_eq!(c.at_depth(2).nth(1).unwrap().0, "d");
+    assert_eq!(c.get().0, "d");
+}
+
+#[test]
+fn paths() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let paths: Vec<_> = c.paths().map(names).collect();
+    assert_eq!(paths, [
+        vec!["a", "b", "c"],
+        vec!["a", "b", "d", "e"],
+        vec!["a", "f"],
+        vec!["a", "g", "h"],
+    ]);
+    c.zero();
+    assert!(c.down());
+    assert!(c.up());
+    assert!(c.down());
+    let paths: Vec<_> = c.paths().map(names).collect();
+    assert_eq!(paths, [vec!["f"]]);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn deep() {
+    let mut t = N("leaf", vec![]);
+    for _ in 0..100_000 {
+        t = N("inner", vec![t]);
+    }
+    {
+        let mut c = TreeCursor::new(&t);
+        assert_eq!(names(c.leaves()), ["leaf"]);
+        assert_eq!(names(c.at_depth(100_000)), ["leaf"]);
+        assert_eq!(c.paths().next().unwrap().len(), 100_001);
+    }
+    // Dropping `t` recursively would overflow the stack.
+    while let Some(child) = t.1.pop() {
+        t = child;
+    }
+}
@@ -0,0 +1,161 @@
+use cursor::TreeCursorMut;
+use journal::{Edit, Journal};
+use prelude::*;
+use std::mem;
+
+#[derive(Debug, PartialEq)]
+struct N(i32, Vec<N>);
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.1.get_mut(idx)
+    }
+}
+
+struct Set(i32);
+
+impl Edit<N> for Set {
+    fn apply(self, node: &mut N) -> Self {
+        Set(mem::replace(&mut node.0, self.0))
+    }
+}
+
+fn t() -> N {
+    N(0, vec![
+        N(1, vec![
+            N(2, vec![]),
+        ]),
+        N(3, vec![]),
+    ])
+}
+
+fn values(n: &N, v: &mut Vec<i32>) {
+    v.push(n.0);
+    for c in &n.1 {
+        values(c, v);
+    }
+}
+
+fn snapshot<'n: 'f, 'f>(j: &mut Journal<'n, 'f, N, Set>) -> Vec<i32> {
+    let pos = j.cursor().pos();
+    while j.cursor().up() { }
+    let mut v = Vec::new();
+    values(j.get(), &mut v);
+    j.cursor().set_pos(&pos);
+    v
+}
+
+#[test]
+fn undo_redo() {
+    let mut t = t();
+    let mut j = Journal::new(TreeCursorMut::new(&mut t));
+    assert!(!j.can_undo());
+    assert!(!j.undo());
+
+    j.apply(Set(10));
+    assert!(j.cursor().down());
+    assert!(j.cursor().down());
+    j.apply(Set(12));
+    assert!(j.cursor().up());
+    assert!(j.cursor().up());
+    assert!(j.cursor().down());
+    j.apply(Set(13));
+    assert_eq!(snapshot(&mut j), [10, 1, 12, 13]);
+
+    assert!(j.undo());
+    assert_eq!(snapshot(&mut j), [10, 1, 12, 3]);
+    assert_eq!(j.get().0, 3);
+    assert!(j.undo());
+    assert_eq!(snapshot(&mut j), [10, 1, 2, 3]);
+    assert_eq!(j.get().0, 2);
+    assert!(j.redo());
+    assert_eq!(snapshot(&mut j), [10, 1, 12, 3]);
+    assert!(j.undo());
+    assert!(j.undo());
+    assert!(!j.undo());
+    assert_eq!(snapshot(&mut j), [0, 1, 2, 3]);
+    assert_eq!(j.get().0, 0);
+
+    // The cursor's "next child" counters come back too.
+    assert!(j.cursor().down());
+    assert_eq!(j.get().0, 1);
+
+    assert!(j.can_redo());
+    j.apply(Set(11));
+    assert!(!j.can_redo());
+    assert!(!j.redo());
+    assert_eq!(snapshot(&mut j), [0, 11, 2, 3]);
+}
+
+#[test]
+fn transactions() {
+    let mut t = t();
+    let mut j = Journal::new(TreeCursorMut::new(&mut t));
+
+    j.begin();
+    assert!(j.in_transaction());
+    j.apply(Set(10));
+    assert!(j.cursor().down());
+    j.apply(Set(11));
+    j.commit();
+    assert!(!j.in_transaction());
+    assert_eq!(snapshot(&mut j), [10, 11, 2, 3]);
+
+    assert!(j.undo());
+    assert_eq!(snapshot(&mut j), [0, 1, 2, 3]);
+    assert_eq!(j.get().0, 0);
+    assert!(j.redo());
+    assert_eq!(snapshot(&mut j), [10, 11, 2, 3]);
+    assert_eq!(j.get().0, 11);
+
+    j.begin();
+    j.apply(Set(21));
+    assert!(j.cursor().down());
+    j.begin();
+    j.apply(Set(22));
+    assert!(j.cursor().up());
+    assert!(j.cursor().up());
+    j.apply(Set(20));
+    assert_eq!(snapshot(&mut j), [20, 21, 22, 3]);
+    j.rollback();
+    assert_eq!(snapshot(&mut j), [10, 21, 2, 3]);
+    assert_eq!(j.get().0, 2);
+    j.commit();
+    assert!(j.undo());
+    assert_eq!(snapshot(&mut j), [10, 11, 2, 3]);
+    // Redoing ends where the last edit that wasn't rolled back was made.
+    assert!(j.redo());
+    assert_eq!(j.get().0, 21);
+    assert!(j.undo());
+    assert!(j.undo());
+    assert_eq!(snapshot(&mut j), [0, 1, 2, 3]);
+
+    j.begin();
+    j.apply(Set(30));
+    j.rollback();
+    assert!(!j.in_transaction());
+    assert_eq!(snapshot(&mut j), [0, 1, 2, 3]);
+
+    // Empty transactions aren't history.
+    j.begin();
+    j.commit();
+    assert!(!j.can_undo());
+}
+
+#[test]
+#[should_panic(expected = "can't be recorded")]
+fn apply_after_down_map() {
+    let mut t = t();
+    let mut j = Journal::new(TreeCursorMut::new(&mut t));
+    assert!(j.cursor().down_map(|n, _| n.1.last_mut()));
+    assert!(!j.cursor().pos_is_exact());
+    j.apply(Set(13));
+}
+
+#[test]
+#[should_panic(expected = "no transaction in progress")]
+fn commit_without_begin() {
+    let mut t = t();
+    let mut j: Journal<N, Set> = Journal::new(TreeCursorMut::new(&mut t));
+    j.commit();
+}
@@ -20,8 +20,8 @@ fn build_link_map<'t>(
     m
 }
 
-fn build_mut_link_map<'t>(
-    nn: &'t mut [(&str, LinkNode)],
+fn build_mut_link_map(
+    nn: &mut [(&str, LinkNode)],
 ) -> HashMap<String, *mut LinkNode> {
     let mut m = HashMap::new();
     for &mut (name, ref mut link) in nn {
@@ -30,6 +30,32 @@ fn build_mut_link_map<'t>(
     m
 }
 
+// Links each node to the next one in `chain`, starting from `root`, by
+// address. Unlike a map keyed by name, this can send two nodes with the same
+// target to different copies of the node they link to.
+fn build_chain_link_map(
+    root: &mut LinkNode,
+    chain: &mut [LinkNode],
+) -> HashMap<*const LinkNode, *mut LinkNode> {
+    let mut m = HashMap::new();
+    let mut parent = root as *mut LinkNode;
+    for link in chain {
+        let link = link as *mut LinkNode;
+        m.insert(parent as *const LinkNode, link);
+        parent = link;
+    }
+    m
+}
+
+// Pins down the closure's signature so that its return value is tied to its
+// argument, as TreeCursorMut::down_map requires.
+fn mut_link_fn<F>(f: F) -> F
+where
+    F: Fn(&mut LinkNode, usize) -> Option<&mut LinkNode>,
+{
+    f
+}
+
 #[test]
 fn link_tree() {
     let root = n("foo");
@@ -39,10 +65,14 @@ fn link_tree() {
         ("fuzz", n("bar")),
     ];
     let mut mroot = root.clone();
-    let mut mnn = nn.clone();
+    // The shared cursor follows the cycle back to a node it already holds, but
+    // a mutable cursor can't hold a node twice: `md` would have to make a new
+    // mutable reference to it while the cursor still uses the old one. So
+    // the mutable cursor follows the cycle unrolled into fresh copies.
+    let mut chain = vec![n("bar"), n("fuzz"), n("bar"), n("fuzz")];
 
     let lm = build_link_map(&nn);
-    let mlm = build_mut_link_map(&mut mnn);
+    let mlm = build_chain_link_map(&mut mroot, &mut chain);
 
     let d = |n: &LinkNode, idx: usize| {
         if idx == 0 {
@@ -51,13 +81,14 @@ fn link_tree() {
             None
         }
     };
-    let md = |n: &mut LinkNode, idx: usize| {
+    let md = mut_link_fn(|n: &mut LinkNode, idx: usize| {
         if idx == 0 {
-            Some(unsafe { mlm[&n.target].as_mut().unwrap() })
+            let link = mlm.get(&(n as *const LinkNode))?;
+            Some(unsafe { link.as_mut().unwrap() })
         } else {
             None
         }
-    };
+    });
 
     let mut c = TreeCursor::new(&root);
     let mut cm = TreeCursorMut::new(&mut mroot);
@@ -152,13 +183,13 @@ fn link_tree_scoped_down() {
             None
         }
     };
-    let md = |n: &mut LinkNode, idx: usize| {
+    let md = mut_link_fn(|n: &mut LinkNode, idx: usize| {
         if idx == 0 {
             Some(unsafe { mlm[&n.target].as_mut().unwrap() })
         } else {
             None
         }
-    };
+    });
 
     let mut c = TreeCursor::new(&root);
     let mut cm = TreeCursorMut::new(&mut mroot);
@@ -1,3 +1,37 @@
+#[cfg(feature = "alloc")]
+mod arena_tree;
+#[cfg(feature = "alloc")]
+mod build;
+#[cfg(feature = "alloc")]
+mod dot;
+#[cfg(feature = "alloc")]
+mod events;
+mod fixed;
+#[cfg(feature = "alloc")]
 mod hashmap_tree;
+#[cfg(feature = "alloc")]
+mod iter;
+#[cfg(feature = "alloc")]
+mod journal;
+#[cfg(feature = "alloc")]
 mod link_tree;
+#[cfg(feature = "alloc")]
+mod observe;
+#[cfg(feature = "alloc")]
+mod persistent;
+#[cfg(feature = "alloc")]
+mod range;
+#[cfg(feature = "alloc")]
+mod render;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "alloc")]
+mod soundness;
+#[cfg(feature = "alloc")]
 mod standard_tree;
+#[cfg(feature = "alloc")]
+mod summary;
+#[cfg(feature = "alloc")]
+mod transform;
+#[cfg(feature = "alloc")]
+mod visit;
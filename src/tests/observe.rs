@@ -0,0 +1,265 @@
+use cursor::{TreeCursor, TreeCursorMut};
+use events::{Event, Events};
+use journal::{Edit, Journal};
+use observe::{Move, Recorder};
+use prelude::*;
+use range::PosRange;
+use std::mem;
+use std::ops::ControlFlow;
+use summary::{Summarize, SummaryCursor};
+use visit::{self, VisitAction, Visitor};
+
+#[derive(Clone)]
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.1.get_mut(idx)
+    }
+}
+
+impl ExactSizeDown for N {
+    fn child_count(&self) -> usize {
+        self.1.len()
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![N("c", vec![]), N("d", vec![N("e", vec![])])]),
+        N("f", vec![]),
+    ])
+}
+
+#[test]
+fn moves() {
+    let t = t();
+    let mut rec = Recorder::new();
+    {
+        let mut c = TreeCursor::with_observer(&t, &mut rec);
+        assert!(c.down());
+        assert!(c.down_nth(1));
+        assert!(c.down());
+        assert!(!c.down());
+        c.zero();
+        assert_eq!(c.to_root(), 3);
+        assert!(!c.up());
+        assert!(c.down_take_cursor().is_some());
+        assert!(c.down());
+        assert!(c.take_cursor().is_some());
+    }
+    assert_eq!(rec.moves(), [
+        Move::Down { depth: 1, idx: 0 },
+        Move::Set { depth: 1, idx: 1 },
+        Move::Down { depth: 2, idx: 1 },
+        Move::Down { depth: 3, idx: 0 },
+        Move::Zero { depth: 3 },
+        Move::Up { depth: 2 },
+        Move::Up { depth: 1 },
+        Move::Up { depth: 0 },
+        Move::Zero { depth: 0 },
+        Move::DownTake { depth: 0, idx: 0 },
+        Move::Down { depth: 1, idx: 1 },
+        Move::Take { depth: 0 },
+    ]);
+    assert!(rec.moves().iter().all(|m| m.depth() <= 3));
+    rec.clear();
+    assert!(rec.moves().is_empty());
+}
+
+#[test]
+fn replay() {
+    let mut t = t();
+    let mut u = N("x", vec![
+        N("y", vec![N("z", vec![]), N("w", vec![N("v", vec![])])]),
+        N("u", vec![N("s", vec![])]),
+    ]);
+    let (rec, pos) = {
+        let mut c = TreeCursorMut::with_observer(&mut t, Recorder::new());
+        assert!(c.down());
+        assert!(c.down());
+        assert!(c.next_sibling());
+        assert!(c.down());
+        let p = c.pos();
+        assert!(c.up_n(2));
+        c.zero();
+        assert!(c.down());
+        c.set_pos(&p);
+        assert_eq!(c.with_ancestor_mut(2, |n| n.1.len()), Some(2));
+        assert!(c.up_until(|n| n.0 == "a"));
+        assert!(c.down());
+        assert_eq!(c.get().0, "f");
+        (c.observer().clone(), c.pos())
+    };
+
+    let mut c = TreeCursorMut::new(&mut u);
+    assert!(rec.replay_mut(&mut c));
+    assert_eq!(c.pos(), pos);
+
+    let mut c = TreeCursor::new(&u);
+    assert!(rec.replay(&mut c));
+    assert_eq!(c.get().0, "u");
+
+    // A tree missing a node the recording moved to can't replay it.
+    let small = N("x", vec![N("y", vec![N("z", vec![])])]);
+    let mut c = TreeCursor::new(&small);
+    assert!(!rec.replay(&mut c));
+}
+
+#[test]
+fn rejoin() {
+    let mut t = t();
+    let (rec, pos) = {
+        let mut c = TreeCursorMut::with_observer(&mut t, Recorder::new());
+        assert!(c.down());
+        c.take_cursor_rejoin(|c| {
+            assert!(c.down_nth(1));
+            assert!(c.down());
+            assert!(c.up());
+        }).unwrap();
+        assert_eq!(c.get().0, "d");
+        c.zero();
+        c.down_take_cursor_rejoin(|c| assert!(!c.down())).unwrap();
+        (c.observer().clone(), c.pos())
+    };
+    let mut u = t.clone();
+    let mut c = TreeCursorMut::new(&mut u);
+    assert!(rec.replay_mut(&mut c));
+    assert_eq!(c.pos(), pos);
+    assert_eq!(c.get().0, "e");
+}
+
+#[test]
+fn observer_ignored_by_eq() {
+    let t = t();
+    let mut a = TreeCursor::with_observer(&t, Recorder::new());
+    let mut b = TreeCursor::with_observer(&t, Recorder::new());
+    assert!(a.down());
+    assert!(a.down());
+    assert!(a.up());
+    assert!(b.down_nth(0));
+    assert!(b.down());
+    assert!(b.up());
+    assert_ne!(a.observer(), b.observer());
+    assert!(a == b);
+    b.observer_mut().clear();
+    assert!(b.observer().moves().is_empty());
+
+    // Converting a `TreeCursorMut` keeps its observer.
+    let mut u = t.clone();
+    let mut cm = TreeCursorMut::with_observer(&mut u, Recorder::new());
+    assert!(cm.down());
+    let mut c = TreeCursor::from(cm);
+    assert!(c.up());
+    assert_eq!(c.observer().moves().len(), 2);
+}
+
+struct Count(usize);
+
+impl Visitor<N> for Count {
+    type Break = ();
+
+    fn enter(&mut self, _node: &N) -> VisitAction {
+        self.0 += 1;
+        VisitAction::Continue
+    }
+}
+
+#[test]
+fn walk() {
+    let t = t();
+    let mut c = TreeCursor::with_observer(&t, Recorder::new());
+    let mut v = Count(0);
+    assert_eq!(visit::walk(&mut c, &mut v), ControlFlow::Continue(()));
+    assert_eq!(v.0, 6);
+    let downs = c.observer().moves().iter()
+        .filter(|m| matches!(**m, Move::Down { .. }))
+        .count();
+    assert_eq!(downs, 5);
+}
+
+#[test]
+fn reset() {
+    let mut t = t();
+    let mut u = t.clone();
+    let mut cm = TreeCursorMut::with_observer(&mut t, Recorder::new());
+    assert!(cm.down());
+    assert!(cm.down());
+    cm.reset(&mut u);
+    assert_eq!(cm.observer().moves()[2..], [
+        Move::Up { depth: 1 },
+        Move::Up { depth: 0 },
+        Move::Zero { depth: 0 },
+    ]);
+}
+
+struct Rename(&'static str);
+
+impl Edit<N> for Rename {
+    fn apply(self, node: &mut N) -> Self {
+        Rename(mem::replace(&mut node.0, self.0))
+    }
+}
+
+impl Summarize for N {
+    fn summarize(&mut self) { }
+}
+
+fn downs(rec: &Recorder) -> usize {
+    rec.moves().iter().filter(|m| matches!(**m, Move::Down { .. })).count()
+}
+
+#[test]
+fn observed_wrappers() {
+    let mut t = t();
+    let mut rec = Recorder::new();
+    {
+        let mut c = TreeCursor::with_observer(&t, &mut rec);
+        let entered = Events::new(&mut c)
+            .filter(|e| matches!(*e, Event::Enter(..)))
+            .count();
+        assert_eq!(entered, 6);
+        let leaves: Vec<_> = c.leaves().map(|n| n.0).collect();
+        assert_eq!(leaves, ["c", "e", "f"]);
+    }
+    assert_eq!(downs(&rec), 10);
+
+    rec.clear();
+    let range = {
+        let mut cm = TreeCursorMut::new(&mut t);
+        assert!(cm.down());
+        assert!(cm.down());
+        let c = cm.pos();
+        assert!(cm.up());
+        assert!(cm.up());
+        assert!(cm.down_nth(1));
+        PosRange::new(c, cm.pos())
+    };
+    {
+        let c = TreeCursor::with_observer(&t, &mut rec);
+        let names: Vec<_> = range.nodes_with(c).map(|n| n.0).collect();
+        assert_eq!(names, ["c", "d", "e", "f"]);
+    }
+    assert_eq!(downs(&rec), 5);
+
+    rec.clear();
+    {
+        let cm = TreeCursorMut::with_observer(&mut t, &mut rec);
+        let mut j = Journal::new(cm);
+        assert!(j.cursor().down());
+        j.apply(Rename("x"));
+        assert!(j.cursor().up());
+        assert!(j.undo());
+        let mut s = SummaryCursor::new(j.into_inner());
+        assert!(s.down());
+        assert_eq!(s.get().0, "c");
+    }
+    assert_eq!(t.1[0].0, "b");
+    assert_eq!(downs(&rec), 4);
+}
@@ -0,0 +1,174 @@
+use cursor::TreeCursor;
+use persistent::PersistentTreeCursor;
+use prelude::*;
+
+struct X {
+    n: u32,
+    v: Vec<X>,
+}
+
+impl Down for X {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.v.get(idx)
+    }
+}
+
+impl ExactSizeDown for X {
+    fn child_count(&self) -> usize {
+        self.v.len()
+    }
+}
+
+fn xx(n: u32, v: Vec<X>) -> X {
+    X { n, v }
+}
+
+fn x(n: u32) -> X {
+    X { n, v: vec![] }
+}
+
+fn tree() -> X {
+    xx(0, vec![
+        xx(1, vec![x(2), xx(3, vec![x(4)])]),
+        x(5),
+        xx(6, vec![x(7)]),
+    ])
+}
+
+#[test]
+fn same_as_tree_cursor() {
+    let t = tree();
+    let mut a = TreeCursor::new(&t);
+    let mut b = PersistentTreeCursor::new(&t);
+    // Walk the whole tree twice, with some redundant `up`s and `zero`s, and
+    // check that both cursors agree at every step.
+    for step in 0..40 {
+        let (da, db) = if step % 7 == 3 {
+            a.zero();
+            b.zero();
+            (a.down(), b.down())
+        } else if step % 3 == 0 {
+            (a.up(), b.up())
+        } else {
+            (a.down(), b.down())
+        };
+        assert_eq!(da, db);
+        assert_eq!(a.get().n, b.get().n);
+    }
+}
+
+#[test]
+fn down_rev_same_as_tree_cursor() {
+    let t = tree();
+    let mut a = TreeCursor::new(&t);
+    let mut b = PersistentTreeCursor::new(&t);
+    for step in 0..20 {
+        let (da, db) = if step % 3 == 2 {
+            (a.up(), b.up())
+        } else {
+            (a.down_rev(), b.down_rev())
+        };
+        assert_eq!(da, db);
+        assert_eq!(a.get().n, b.get().n);
+    }
+}
+
+#[test]
+fn fork() {
+    let t = tree();
+    let mut c = PersistentTreeCursor::new(&t);
+    assert!(c.down());
+    assert!(c.down());
+    let mut f = c.clone();
+    // The forks' counters are independent.
+    assert!(c.up());
+    assert!(c.down());
+    assert_eq!(c.get().n, 3);
+    assert!(f.up());
+    assert!(f.down());
+    assert_eq!(f.get().n, 3);
+    assert!(f.down());
+    assert_eq!(f.get().n, 4);
+    // So are the shared ancestors' counters.
+    assert!(c.up());
+    assert!(c.up());
+    assert!(c.down());
+    assert_eq!(c.get().n, 5);
+    assert!(f.up());
+    assert!(f.up());
+    assert!(f.up());
+    assert!(f.down());
+    assert_eq!(f.get().n, 5);
+}
+
+#[test]
+fn backtrack() {
+    // Collect every root-to-leaf path by forking at each branch point.
+    fn paths(c: PersistentTreeCursor<X>, path: &mut Vec<u32>,
+             out: &mut Vec<Vec<u32>>) {
+        path.push(c.get().n);
+        let mut c = c;
+        let mut leaf = true;
+        while c.down() {
+            leaf = false;
+            paths(c.clone(), path, out);
+            assert!(c.up());
+        }
+        if leaf {
+            out.push(path.clone());
+        }
+        path.pop();
+    }
+
+    let t = tree();
+    let mut out = vec![];
+    paths(PersistentTreeCursor::new(&t), &mut vec![], &mut out);
+    assert_eq!(out, vec![
+        vec![0, 1, 2],
+        vec![0, 1, 3, 4],
+        vec![0, 5],
+        vec![0, 6, 7],
+    ]);
+}
+
+#[test]
+fn take_cursor() {
+    let t = tree();
+    let mut c = PersistentTreeCursor::new(&t);
+    assert!(c.take_cursor().is_none());
+    assert!(c.down());
+    {
+        let mut d = c.take_cursor().unwrap();
+        assert_eq!(d.get().n, 1);
+        assert!(!d.up());
+        assert!(d.down());
+        assert_eq!(d.get().n, 2);
+    }
+    assert_eq!(c.get().n, 0);
+    {
+        let d = c.down_take_cursor().unwrap();
+        assert_eq!(d.get().n, 5);
+    }
+    assert!(c.down());
+    assert_eq!(c.get().n, 6);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn deep() {
+    let mut t = x(0);
+    for n in 1..100_000 {
+        t = xx(n, vec![t]);
+    }
+    let mut c = PersistentTreeCursor::new(&t);
+    while c.down() { }
+    assert_eq!(c.get().n, 0);
+    let f = c.clone();
+    drop(c);
+    assert_eq!(f.get().n, 0);
+    drop(f);
+    // `t` itself would overflow the stack when dropped.
+    while let Some(child) = t.v.pop() {
+        t = child;
+    }
+}
@@ -0,0 +1,157 @@
+use cursor::{TreeCursorMut, TreeCursorPos};
+use prelude::*;
+use range::PosRange;
+
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.1.get_mut(idx)
+    }
+}
+
+impl ExactSizeDown for N {
+    fn child_count(&self) -> usize {
+        self.1.len()
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+            N("d", vec![N("e", vec![])]),
+        ]),
+        N("f", vec![]),
+        N("g", vec![N("h", vec![])]),
+    ])
+}
+
+fn pos(t: &mut N, path: &[usize]) -> TreeCursorPos {
+    let mut c = TreeCursorMut::new(t);
+    for &i in path {
+        assert!(c.down_nth(i));
+    }
+    c.pos()
+}
+
+fn name(t: &mut N, p: &TreeCursorPos) -> &'static str {
+    let mut c = TreeCursorMut::new(t);
+    c.set_pos(p);
+    c.get().0
+}
+
+fn nodes(t: &N, r: &PosRange) -> Vec<&'static str> {
+    r.nodes(t).map(|n| n.0).collect()
+}
+
+fn cover(t: &mut N, r: &PosRange) -> Vec<&'static str> {
+    let subtrees = r.covering_subtrees(t);
+    subtrees.iter().map(|p| name(t, p)).collect()
+}
+
+#[test]
+fn nodes_between() {
+    let mut t = t();
+    let c = pos(&mut t, &[0, 0]);
+    let e = pos(&mut t, &[0, 1, 0]);
+    let h = pos(&mut t, &[2, 0]);
+    let root = pos(&mut t, &[]);
+
+    let r = PosRange::new(h.clone(), c.clone());
+    assert_eq!((r.start(), r.end()), (&c, &h));
+    assert_eq!(nodes(&t, &r), ["c", "d", "e", "f", "g", "h"]);
+    assert_eq!(nodes(&t, &PosRange::new(c.clone(), e.clone())),
+               ["c", "d", "e"]);
+    assert_eq!(nodes(&t, &PosRange::new(e.clone(), e.clone())), ["e"]);
+    assert_eq!(nodes(&t, &PosRange::new(root.clone(), h.clone())).len(), 8);
+
+    assert!(r.contains(&c));
+    assert!(r.contains(&pos(&mut t, &[1])));
+    assert!(r.contains(&h));
+    assert!(!r.contains(&root));
+    assert!(!r.contains(&pos(&mut t, &[0])));
+
+    // The ends' own counters don't matter.
+    let b_visited = {
+        let mut cm = TreeCursorMut::new(&mut t);
+        assert!(cm.down());
+        assert!(cm.down());
+        assert!(cm.up());
+        cm.pos()
+    };
+    assert_ne!(b_visited, pos(&mut t, &[0]));
+    let r = PosRange::new(b_visited, c.clone());
+    assert_eq!(nodes(&t, &r), ["b", "c"]);
+    assert!(r.contains(&pos(&mut t, &[0])));
+}
+
+#[test]
+fn nodes_past_end_of_tree() {
+    let mut t = t();
+    let mut big = N("a", vec![N("b", vec![]), N("f", vec![]),
+                             N("g", vec![]), N("i", vec![])]);
+    let f = pos(&mut t, &[1]);
+    let i = pos(&mut big, &[3]);
+    assert_eq!(nodes(&t, &PosRange::new(f, i)), ["f", "g", "h"]);
+}
+
+#[test]
+fn nodes_missing_start() {
+    let mut big = N("a", vec![N("b", vec![]), N("f", vec![]),
+                             N("g", vec![]), N("i", vec![])]);
+    let i = pos(&mut big, &[3]);
+    assert!(nodes(&t(), &PosRange::new(i.clone(), i)).is_empty());
+}
+
+#[test]
+fn covering_subtrees() {
+    let mut t = t();
+    let a = pos(&mut t, &[]);
+    let b = pos(&mut t, &[0]);
+    let c = pos(&mut t, &[0, 0]);
+    let e = pos(&mut t, &[0, 1, 0]);
+    let f = pos(&mut t, &[1]);
+    let h = pos(&mut t, &[2, 0]);
+
+    assert_eq!(cover(&mut t, &PosRange::new(c.clone(), h.clone())),
+               ["c", "d", "f", "g"]);
+    assert_eq!(cover(&mut t, &PosRange::new(e.clone(), f.clone())),
+               ["e", "f"]);
+    assert_eq!(cover(&mut t, &PosRange::new(c.clone(), e.clone())),
+               ["c", "d"]);
+    assert_eq!(cover(&mut t, &PosRange::new(b.clone(), e.clone())), ["b"]);
+    assert_eq!(cover(&mut t, &PosRange::new(e.clone(), e.clone())), ["e"]);
+    assert_eq!(cover(&mut t, &PosRange::new(h.clone(), a.clone())), ["a"]);
+
+    // Ancestors of the end whose subtrees reach past it aren't covered.
+    assert_eq!(cover(&mut t, &PosRange::new(a.clone(), e.clone())), ["b"]);
+    assert_eq!(cover(&mut t, &PosRange::new(a.clone(), c.clone())), ["c"]);
+
+    // Every node in the range is in exactly one of the covering subtrees,
+    // and every node in those is in the range.
+    let r = PosRange::new(e.clone(), h.clone());
+    let subtrees = r.covering_subtrees(&t);
+    assert_eq!(cover(&mut t, &r), ["e", "f", "g"]);
+    let all = [a, b, c, pos(&mut t, &[0, 1]), e, f, pos(&mut t, &[2]), h];
+    for p in all.iter() {
+        let n = subtrees.iter().filter(|s| {
+            TreeCursorPos::common_ancestor(s, p) == **s
+        }).count();
+        assert_eq!(n, if r.contains(p) { 1 } else { 0 });
+    }
+}
+
+#[test]
+fn covering_subtrees_missing_start() {
+    let mut big = N("a", vec![N("b", vec![]), N("f", vec![]),
+                             N("g", vec![]), N("i", vec![])]);
+    let i = pos(&mut big, &[3]);
+    assert!(PosRange::new(i.clone(), i).covering_subtrees(&t()).is_empty());
+}
@@ -0,0 +1,113 @@
+use cursor::TreeCursor;
+use observe::{Move, Recorder};
+use prelude::*;
+use render::{Charset, Renderer};
+
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+            N("d", vec![
+                N("e", vec![]),
+            ]),
+        ]),
+        N("f", vec![
+            N("g", vec![]),
+        ]),
+    ])
+}
+
+fn label(n: &N) -> String {
+    n.0.to_string()
+}
+
+#[test]
+fn full() {
+    let t = t();
+    assert_eq!(Renderer::new(label).render(&t), "\
+a
+├── b
+│   ├── c
+│   └── d
+│       └── e
+└── f
+    └── g
+");
+    assert_eq!(Renderer::new(label).charset(Charset::Ascii).render(&t), "\
+a
+|-- b
+|   |-- c
+|   `-- d
+|       `-- e
+`-- f
+    `-- g
+");
+}
+
+#[test]
+fn single_node() {
+    assert_eq!(Renderer::new(label).render(&N("a", vec![])), "a\n");
+}
+
+#[test]
+fn max_depth_and_collapse() {
+    let t = t();
+    assert_eq!(Renderer::new(label).max_depth(1).render(&t), "\
+a
+├── b …
+└── f …
+");
+    assert_eq!(Renderer::new(label).max_depth(0).render(&t), "a …\n");
+    assert_eq!(Renderer::new(label).collapse(|n| n.0 == "b").render(&t), "\
+a
+├── b …
+└── f
+    └── g
+");
+}
+
+#[test]
+fn highlight() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    assert!(c.down());
+    assert!(c.down());
+    assert!(c.up());
+    assert!(c.down());
+    assert_eq!(Renderer::new(label).highlight(&c).render(&t), "\
+a
+├── b
+│   ├── c
+│   └── d ◀
+│       └── e
+└── f
+    └── g
+");
+
+    // A cursor rooted elsewhere doesn't match anything.
+    let c = TreeCursor::new(&t.1[1]);
+    assert!(!Renderer::new(label).highlight(&c).render(&t).contains('◀'));
+}
+
+#[test]
+fn highlight_observed() {
+    let t = t();
+    let mut c = TreeCursor::with_observer(&t, Recorder::new());
+    assert!(c.down());
+    assert!(c.down());
+    let s = Renderer::new(label).highlight(&c).render(&t);
+    assert!(s.contains("c ◀"));
+    assert_eq!(s.matches('◀').count(), 1);
+    assert_eq!(c.observer().moves(), [
+        Move::Down { depth: 1, idx: 0 },
+        Move::Down { depth: 2, idx: 0 },
+    ]);
+}
@@ -0,0 +1,95 @@
+use cursor::{RelativePos, TreeCursorMut, TreeCursorPos};
+use prelude::*;
+use ser::{Flat, Nested};
+use serde_json;
+
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.1.get_mut(idx)
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+        ]),
+        N("d", vec![]),
+    ])
+}
+
+#[test]
+fn nested() {
+    let t = t();
+    assert_eq!(
+        serde_json::to_string(&Nested::new(&t, |n: &N| n.0)).unwrap(),
+        concat!(
+            r#"{"label":"a","children":["#,
+            r#"{"label":"b","children":[{"label":"c","children":[]}]},"#,
+            r#"{"label":"d","children":[]}]}"#,
+        ),
+    );
+}
+
+#[test]
+fn flat() {
+    let t = t();
+    assert_eq!(
+        serde_json::to_string(&Flat::new(&t, |n: &N| n.0)).unwrap(),
+        r#"[[0,"a"],[1,"b"],[2,"c"],[1,"d"]]"#,
+    );
+}
+
+#[test]
+fn flat_deep() {
+    let mut t = N("leaf", vec![]);
+    for _ in 0..100_000 {
+        t = N("x", vec![t]);
+    }
+    let s = serde_json::to_string(&Flat::new(&t, |n: &N| n.0)).unwrap();
+    assert!(s.ends_with(r#"[100000,"leaf"]]"#));
+
+    // Dropping the tree recursively would overflow the stack.
+    let mut v = vec![t];
+    while let Some(mut n) = v.pop() {
+        v.append(&mut n.1);
+    }
+}
+
+#[test]
+fn pos() {
+    let mut t = t();
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    assert!(cm.down());
+    let s = serde_json::to_string(&cm.pos()).unwrap();
+    assert_eq!(s, "[1,1,0]");
+
+    let p: TreeCursorPos = serde_json::from_str(&s).unwrap();
+    assert!(cm.up());
+    assert!(cm.up());
+    cm.set_pos(&p);
+    assert_eq!(cm.get().0, "c");
+
+    assert!(serde_json::from_str::<TreeCursorPos>("[]").is_err());
+    assert!(serde_json::from_str::<TreeCursorPos>("[0,0]").is_err());
+    assert!(serde_json::from_str::<TreeCursorPos>("[1,0,2]").is_err());
+
+    let base = p.clone();
+    assert!(cm.up());
+    let rel = p.relative_to(&cm.pos()).unwrap();
+    let s = serde_json::to_string(&rel).unwrap();
+    assert_eq!(s, "[1,0]");
+    let rel: RelativePos = serde_json::from_str(&s).unwrap();
+    assert_eq!(cm.pos().join(&rel), base);
+    assert!(serde_json::from_str::<RelativePos>("[]").is_err());
+    assert!(serde_json::from_str::<RelativePos>("[0,1]").is_err());
+}
@@ -0,0 +1,246 @@
+// Adversarial tests for TreeCursorMut's unsafe code. These pass with plain
+// `cargo test`, but they're really meant to be run under Miri, with both
+// aliasing models:
+//
+//     cargo +nightly miri test
+//     MIRIFLAGS=-Zmiri-tree-borrows cargo +nightly miri test
+
+use cursor::{TreeCursor, TreeCursorMut};
+use prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+struct X {
+    n: usize,
+    v: Vec<X>,
+}
+
+impl Down for X {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.v.get(idx)
+    }
+}
+
+impl DownMut for X {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.v.get_mut(idx)
+    }
+}
+
+fn xx(n: usize, v: Vec<X>) -> X {
+    X { n, v }
+}
+
+fn x(n: usize) -> X {
+    xx(n, vec![])
+}
+
+fn t() -> X {
+    xx(0, vec![
+        xx(1, vec![
+            x(2),
+            x(3),
+        ]),
+        x(4),
+    ])
+}
+
+#[test]
+fn interleaved_get_and_get_mut() {
+    let mut t = t();
+    {
+        let mut cm = TreeCursorMut::new(&mut t);
+        for _ in 0..2 {
+            let a = cm.get().n;
+            cm.get_mut().n += 10;
+            assert_eq!(cm.get().n, a + 10);
+            assert!(cm.down());
+        }
+        cm.get_mut().n += 10;
+        assert!(cm.up());
+        cm.get_mut().n += 10;
+        assert!(cm.down());
+        cm.get_mut().n += 10;
+        assert_eq!(cm.get().n, 13);
+        assert!(cm.up());
+        assert!(cm.up());
+        assert_eq!(cm.get().n, 10);
+    }
+    assert_eq!(t, xx(10, vec![
+        xx(21, vec![
+            x(12),
+            x(13),
+        ]),
+        x(4),
+    ]));
+}
+
+#[test]
+fn reallocate_children() {
+    let mut t = t();
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    assert!(cm.down());
+    cm.get_mut().n = 20;
+    assert!(cm.up());
+    // Moves the node the cursor was just at.
+    for i in 0..32 {
+        cm.get_mut().v.push(x(100 + i));
+    }
+    cm.zero();
+    assert!(cm.down());
+    assert_eq!(cm.get().n, 20);
+    cm.get_mut().n = 21;
+    assert!(cm.up());
+    assert!(cm.up());
+    cm.get_mut().v.clear();
+    assert!(!cm.down());
+}
+
+#[test]
+fn down_map_to_self() {
+    let mut t = t();
+    {
+        let mut cm = TreeCursorMut::new(&mut t);
+        assert!(cm.down_map(|n, idx| if idx < 3 { Some(n) } else { None }));
+        assert!(cm.down_map(|n, _| Some(n)));
+        cm.get_mut().n += 1;
+        assert!(cm.up());
+        cm.get_mut().n += 1;
+        assert!(cm.down());
+        cm.get_mut().n += 1;
+        assert!(cm.up());
+        assert!(cm.up());
+        cm.get_mut().n += 1;
+        assert!(cm.down_map(|n, idx| if idx < 3 { Some(n) } else { None }));
+        assert_eq!(cm.get().n, 3);
+    }
+    assert_eq!(t.n, 3);
+    assert_eq!(t.v[1].n, 5);
+}
+
+#[test]
+fn as_cursor_then_mutate() {
+    let mut t = t();
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    {
+        let mut c = cm.as_cursor();
+        assert_eq!(c.get().n, 1);
+        assert!(c.down());
+        assert_eq!(c.get().n, 2);
+        assert!(c.up());
+        assert!(c.down());
+        assert_eq!(c.get().n, 3);
+        assert!(c.up());
+        assert!(!c.up());
+        assert_eq!(c.get().n, 1);
+    }
+    cm.get_mut().n = 11;
+    assert!(cm.down());
+    cm.get_mut().n = 12;
+    {
+        let c = cm.as_cursor();
+        let c2 = cm.as_cursor();
+        assert_eq!(c.get().n, 12);
+        assert_eq!(c2.get().n, 12);
+    }
+    assert!(cm.up());
+    assert_eq!(cm.get().n, 11);
+}
+
+// Reading an ancestor through the shared cursor would freeze the pointer to
+// the active node, which here is also the pointer to its aliased parent.
+#[test]
+fn as_cursor_aliased_path() {
+    let mut t = t();
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down_map(|n, _| Some(n)));
+    cm.get_mut().n = 1;
+    {
+        let mut c = cm.as_cursor();
+        assert!(c.peek_parent().is_none());
+        assert!(!c.up());
+        assert_eq!(c.get().n, 1);
+    }
+    cm.get_mut().n = 2;
+    assert!(cm.up());
+    assert_eq!(cm.get().n, 2);
+}
+
+#[test]
+fn from_mut_after_mutation() {
+    let mut t = t();
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    cm.get_mut().n = 11;
+    assert!(cm.down());
+    cm.get_mut().n = 12;
+    let mut c = TreeCursor::from(cm);
+    assert_eq!(c.get().n, 12);
+    assert!(c.up());
+    assert_eq!(c.get().n, 11);
+    assert!(c.down());
+    assert_eq!(c.get().n, 3);
+    assert!(c.up());
+    assert!(c.up());
+    assert_eq!(c.get().n, 0);
+}
+
+#[test]
+fn nested_taken_cursors() {
+    let mut t = t();
+    {
+        let mut cm = TreeCursorMut::new(&mut t);
+        assert!(cm.down());
+        {
+            let mut cm2 = cm.take_cursor().unwrap();
+            cm2.get_mut().n = 11;
+            {
+                let mut cm3 = cm2.down_take_cursor().unwrap();
+                cm3.get_mut().n = 12;
+            }
+            cm2.get_mut().n += 10;
+            {
+                let mut cm3 = cm2.down_map_take_cursor(|n, i| {
+                    n.v.get_mut(i)
+                }).unwrap();
+                cm3.get_mut().n = 13;
+                assert!(!cm3.down());
+            }
+            assert_eq!(cm2.get().n, 21);
+        }
+        cm.get_mut().n = 10;
+        cm.zero();
+        assert!(cm.down());
+        assert!(cm.down());
+        assert_eq!(cm.get().n, 12);
+        cm.get_mut().n += 10;
+    }
+    assert_eq!(t, xx(10, vec![
+        xx(21, vec![
+            x(22),
+            x(13),
+        ]),
+        x(4),
+    ]));
+}
+
+#[test]
+fn set_pos_after_mutation() {
+    let mut t = t();
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    assert!(cm.down());
+    assert!(cm.up());
+    assert!(cm.down());
+    let p = cm.pos();
+    cm.get_mut().n = 13;
+    assert!(cm.up());
+    assert!(cm.up());
+    cm.get_mut().v[0].v[1] = x(30);
+    cm.set_pos(&p);
+    assert_eq!(cm.get().n, 30);
+    cm.get_mut().n = 31;
+    cm.set_pos(&p);
+    assert_eq!(cm.get().n, 31);
+}
@@ -1,4 +1,4 @@
-use cursor::{TreeCursor, TreeCursorMut};
+use cursor::{RelativePos, TreeCursor, TreeCursorMut, TreeCursorPos};
 use prelude::*;
 use std::ptr;
 
@@ -19,6 +19,12 @@ impl DownMut for X {
     }
 }
 
+impl ExactSizeDown for X {
+    fn child_count(&self) -> usize {
+        self.v.len()
+    }
+}
+
 fn xx(v: Vec<X>) -> X {
     X { v }
 }
@@ -454,7 +460,7 @@ fn iter_down() {
 
     let mut leaf_count = 0;
     while let Some(mut c) = c.down_take_cursor() {
-        while let Some(_) = c.down_take_cursor() {
+        while c.down_take_cursor().is_some() {
             leaf_count += 1;
         }
     }
@@ -508,3 +514,671 @@ fn set_pos_error() {
     assert!(cm.up());
     assert_eq!(cm.get().v.len(), 3);
 }
+
+fn chain(depth: usize) -> X {
+    let mut t = x();
+    for _ in 0..depth {
+        t = xx(vec![t]);
+    }
+    t
+}
+
+#[test]
+fn deep() {
+    let t = chain(40);
+    let mut mt = t.clone();
+
+    let mut c = TreeCursor::new(&t);
+    let mut cm = TreeCursorMut::new(&mut mt);
+    let mut depth = 0;
+    while c.down() {
+        assert!(cm.down());
+        depth += 1;
+    }
+    assert!(!cm.down());
+    assert_eq!(depth, 40);
+    let p = cm.pos();
+
+    {
+        let mut c2 = cm.as_cursor();
+        assert!(!c2.down());
+        assert!(!c2.up());
+        assert!(c2.get().v.is_empty());
+    }
+    assert!(cm.get().v.is_empty());
+
+    while c.up() {
+        assert!(cm.up());
+        depth -= 1;
+    }
+    assert!(!cm.up());
+    assert_eq!(depth, 0);
+
+    cm.set_pos(&p);
+    assert!(cm.get().v.is_empty());
+    let mut c2 = TreeCursor::from(cm);
+    assert!(c2.get().v.is_empty());
+    assert!(c2.up());
+    assert!(!c2.down());
+}
+
+#[test]
+fn reset() {
+    let t = chain(40);
+    let u = xx(vec![x(), x()]);
+    let mut mt = t.clone();
+    let mut mu = u.clone();
+
+    let mut c = TreeCursor::new(&t);
+    while c.down() { }
+    c.reset(&u);
+    assert!(ptr::eq(c.get(), &u));
+    assert!(c.down());
+    assert!(c.up());
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &u.v[1]));
+    assert!(c.up());
+    assert!(!c.up());
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    while cm.down() { }
+    cm.reset(&mut mu);
+    assert_eq!(cm.get().v.len(), 2);
+    assert!(cm.down());
+    assert!(cm.up());
+    assert!(cm.down());
+    assert!(cm.get().v.is_empty());
+    assert!(cm.up());
+    assert!(!cm.up());
+}
+
+#[test]
+fn down_rev() {
+    let t = xx(vec![
+        x(),
+        xx(vec![x()]),
+        xx(vec![x(), x()]),
+    ]);
+    let mut mt = t.clone();
+
+    let mut c = TreeCursor::new(&t);
+    let mut lens = vec![];
+    while c.down_rev() {
+        lens.push(c.get().v.len());
+        assert!(c.up());
+    }
+    assert_eq!(lens, [2, 1, 0]);
+    c.zero();
+    assert!(c.down_rev());
+    assert!(c.down_rev());
+    assert!(ptr::eq(c.get(), &t.v[2].v[1]));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    while cm.down_rev() {
+        cm.get_mut().v.push(x());
+        assert!(cm.up());
+    }
+    assert!(!cm.up());
+    assert!(cm.down_rev());
+    assert_eq!(cm.get().v.len(), 3);
+    assert!(cm.up());
+    cm.zero();
+    assert!(cm.down());
+    assert_eq!(cm.get().v.len(), 1);
+    assert!(cm.up());
+    assert!(cm.down());
+    assert_eq!(cm.get().v.len(), 2);
+    drop(cm);
+    assert_eq!(
+        mt.v.iter().map(|n| n.v.len()).collect::<Vec<_>>(),
+        [1, 2, 3],
+    );
+}
+
+#[test]
+fn down_rev_counters() {
+    let mut t = xx(vec![
+        x(),
+        xx(vec![x()]),
+        xx(vec![x(), x()]),
+    ]);
+
+    {
+        let mut c = TreeCursor::new(&t);
+        assert!(c.down_rev());
+        assert_eq!(c.index_in_parent(), Some(2));
+        assert!(c.down_rev());
+        assert_eq!(c.index_in_parent(), Some(1));
+        assert!(c.prev_sibling());
+        assert!(ptr::eq(c.get(), &t.v[2].v[0]));
+        assert!(c.up());
+        assert!(c.up());
+        assert!(c.down_rev());
+        assert!(ptr::eq(c.get(), &t.v[1]));
+        assert_eq!(c.index_in_parent(), Some(1));
+    }
+
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down_rev());
+    assert!(cm.down_rev());
+    let pos = cm.pos();
+    assert_eq!(pos.path(), [3, 2]);
+    cm.get_mut().v.push(x());
+    cm.to_root();
+    cm.set_pos(&pos);
+    assert_eq!(cm.get().v.len(), 1);
+    assert_eq!(cm.index_in_parent(), Some(1));
+    drop(cm);
+    assert_eq!(t.v[2].v[1].v.len(), 1);
+}
+
+#[test]
+fn pos_is_exact() {
+    let mut t = xx(vec![xx(vec![xx(vec![x()])])]);
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    assert!(cm.pos_is_exact());
+    assert!(cm.down_map(|n, _| n.v.last_mut()));
+    assert!(!cm.pos_is_exact());
+    assert!(cm.up());
+    assert!(cm.pos_is_exact());
+
+    cm.zero();
+    assert!(cm.down_take_cursor_rejoin(|c| assert!(c.down_map(
+        |n, _| n.v.first_mut(),
+    ))).is_some());
+    assert!(!cm.pos_is_exact());
+    cm.to_root();
+    assert!(cm.down_map(|n, _| n.v.first_mut()));
+    assert!(cm.take_cursor_rejoin(|c| assert!(c.down())).is_some());
+    assert!(!cm.pos_is_exact());
+    assert!(cm.up());
+    assert!(!cm.pos_is_exact());
+    assert!(cm.up());
+    assert!(cm.pos_is_exact());
+}
+
+#[test]
+fn exact_size() {
+    let t = xx(vec![
+        x(),
+        xx(vec![x()]),
+        xx(vec![x(), x()]),
+    ]);
+    let mut mt = t.clone();
+
+    let mut c = TreeCursor::new(&t);
+    assert_eq!(c.index_in_parent(), None);
+    assert!(c.is_last_child());
+    assert!(!c.next_sibling());
+    assert!(!c.down_nth(3));
+    assert!(c.down_last());
+    assert!(ptr::eq(c.get(), &t.v[2]));
+    assert_eq!(c.index_in_parent(), Some(2));
+    assert!(c.is_last_child());
+    assert!(!c.next_sibling());
+    assert!(c.prev_sibling());
+    assert!(ptr::eq(c.get(), &t.v[1]));
+    assert!(!c.is_last_child());
+    assert!(c.prev_sibling());
+    assert_eq!(c.index_in_parent(), Some(0));
+    assert!(!c.prev_sibling());
+    assert!(ptr::eq(c.get(), &t.v[0]));
+    assert!(!c.down_last());
+    assert!(c.next_sibling());
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[1].v[0]));
+    assert!(c.up());
+    assert!(c.up());
+    // The counters are left as `down` would leave them.
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[2]));
+    assert!(c.up());
+    assert!(c.down_nth(1));
+    assert!(c.up());
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[2]));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    assert!(!cm.down_nth(3));
+    assert!(cm.down_last());
+    cm.get_mut().v.clear();
+    assert!(!cm.next_sibling());
+    assert!(cm.prev_sibling());
+    assert!(cm.prev_sibling());
+    assert!(!cm.prev_sibling());
+    cm.get_mut().v.push(x());
+    assert!(cm.next_sibling());
+    assert_eq!(cm.index_in_parent(), Some(1));
+    let p = cm.pos();
+    assert!(cm.up());
+    cm.set_pos(&p);
+    assert_eq!(cm.index_in_parent(), Some(1));
+    drop(cm);
+    assert_eq!(
+        mt.v.iter().map(|n| n.v.len()).collect::<Vec<_>>(),
+        [1, 1, 0],
+    );
+}
+
+#[test]
+fn siblings_after_down_map() {
+    let t = xx(vec![xx(vec![x()]), xx(vec![x(), x()])]);
+    let mut mt = t.clone();
+
+    // With the counter past the last child, `prev_sibling` has nowhere to
+    // go and stays put.
+    let mut c = TreeCursor::new(&t);
+    for _ in 0..3 {
+        assert!(c.down_map(|n, _| n.v.first()));
+        assert!(c.up());
+    }
+    assert!(c.down_map(|n, _| n.v.first()));
+    assert_eq!(c.index_in_parent(), Some(3));
+    assert!(!c.prev_sibling());
+    assert!(ptr::eq(c.get(), &t.v[0]));
+    assert!(c.prev_preorder());
+    assert!(ptr::eq(c.get(), &t));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    assert!(cm.down_map(|n, _| n.v.first_mut()));
+    assert!(cm.down());
+    assert!(cm.up());
+    assert!(!cm.next_sibling());
+    assert!(!cm.prev_sibling());
+    // The cursor didn't move, and its counter is intact.
+    assert!(!cm.down());
+    assert!(cm.up());
+    assert!(cm.down_map(|n, _| n.v.last_mut()));
+    assert!(!cm.prev_sibling());
+    assert_eq!(cm.get().v.len(), 2);
+
+    // Without `down_map`, finding out there's no next sibling leaves the
+    // counter alone too.
+    cm.to_root();
+    assert!(cm.down_last());
+    assert!(cm.down());
+    assert!(cm.up());
+    assert!(!cm.next_sibling());
+    assert_eq!(cm.index_in_parent(), Some(1));
+    assert!(cm.down());
+    assert_eq!(cm.index_in_parent(), Some(1));
+}
+
+#[test]
+fn combinators() {
+    let t = xx(vec![
+        xx(vec![chain(3)]),
+        x(),
+    ]);
+    let mut mt = t.clone();
+
+    let mut c = TreeCursor::new(&t);
+    assert_eq!(c.down_while(|n| !n.v.is_empty()), 5);
+    assert!(c.get().v.is_empty());
+    assert!(!c.up_n(6));
+    assert!(c.up_n(2));
+    assert!(ptr::eq(c.get(), &t.v[0].v[0].v[0]));
+    assert!(c.up_until(|n| n.v.len() == 2));
+    assert!(ptr::eq(c.get(), &t));
+    assert!(!c.up_until(|_| true));
+    // The root's counter survived all of that.
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[1]));
+    assert!(c.up());
+    assert!(c.up_n(0));
+    c.zero();
+    assert_eq!(c.down_while(|n| n.v.len() != 1), 1);
+    assert!(ptr::eq(c.get(), &t.v[0]));
+    assert!(c.skip_subtree());
+    assert!(ptr::eq(c.get(), &t.v[1]));
+    assert!(!c.skip_subtree());
+    assert!(ptr::eq(c.get(), &t));
+    assert!(c.down());
+    assert!(c.down());
+    assert!(c.down());
+    assert_eq!(c.to_root(), 3);
+    assert_eq!(c.to_root(), 0);
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[1]));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    assert_eq!(cm.down_while(|n| !n.v.is_empty()), 5);
+    cm.get_mut().v.push(x());
+    assert!(cm.up_until(|n| n.v.len() == 1));
+    assert!(cm.skip_subtree());
+    assert!(cm.get().v.is_empty());
+    cm.get_mut().v.push(x());
+    assert_eq!(cm.to_root(), 1);
+    assert!(cm.up_n(0));
+    assert!(!cm.up_n(1));
+    assert!(!cm.down());
+    drop(cm);
+    assert_eq!(mt.v[1].v.len(), 1);
+    assert_eq!(mt.v[0].v[0].v[0].v[0].v[0].v.len(), 1);
+}
+
+#[test]
+fn preorder() {
+    let t = xx(vec![
+        xx(vec![x(), xx(vec![x()])]),
+        x(),
+        xx(vec![x()]),
+    ]);
+    let mut mt = t.clone();
+    let order: Vec<*const X> = vec![
+        &t, &t.v[0], &t.v[0].v[0], &t.v[0].v[1], &t.v[0].v[1].v[0], &t.v[1],
+        &t.v[2], &t.v[2].v[0],
+    ];
+
+    let mut c = TreeCursor::new(&t);
+    let mut seen = vec![c.get() as *const X];
+    while c.next_preorder() {
+        seen.push(c.get());
+    }
+    assert_eq!(seen, order);
+    assert!(ptr::eq(c.get(), &t));
+    assert!(!c.prev_preorder());
+
+    // Walk forward to the end, then back to the start.
+    while c.next_preorder() {
+        if ptr::eq(c.get(), order[7]) {
+            break;
+        }
+    }
+    let mut seen = vec![c.get() as *const X];
+    while c.prev_preorder() {
+        seen.push(c.get());
+    }
+    seen.reverse();
+    assert_eq!(seen, order);
+
+    // Mix the two directions.
+    for _ in 0..4 {
+        assert!(c.next_preorder());
+    }
+    assert!(ptr::eq(c.get(), order[4]));
+    assert!(c.prev_preorder());
+    assert!(c.prev_preorder());
+    assert!(ptr::eq(c.get(), order[2]));
+    assert!(c.next_preorder());
+    assert!(c.next_preorder());
+    assert!(c.next_preorder());
+    assert!(ptr::eq(c.get(), order[5]));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    let mut count = 1;
+    while cm.next_preorder() {
+        cm.get_mut().v.clear();
+        count += 1;
+    }
+    assert_eq!(count, 4);
+    assert!(cm.down_last());
+    assert!(cm.prev_preorder());
+    assert!(cm.prev_preorder());
+    assert!(cm.prev_preorder());
+    assert!(!cm.prev_preorder());
+    drop(cm);
+    assert!(mt.v.iter().all(|n| n.v.is_empty()));
+}
+
+#[test]
+fn peek() {
+    let t = xx(vec![
+        x(),
+        xx(vec![x()]),
+    ]);
+    let mut mt = t.clone();
+
+    let mut c = TreeCursor::new(&t);
+    assert!(c.peek_parent().is_none());
+    assert!(ptr::eq(c.peek_child(1).unwrap(), &t.v[1]));
+    assert!(c.peek_child(2).is_none());
+    assert!(ptr::eq(c.peek_next_child().unwrap(), &t.v[0]));
+    assert!(ptr::eq(c.peek_next_child().unwrap(), &t.v[0]));
+    assert!(c.down());
+    assert!(ptr::eq(c.peek_parent().unwrap(), &t));
+    assert!(c.peek_next_child().is_none());
+    assert!(c.up());
+    assert!(ptr::eq(c.peek_next_child().unwrap(), &t.v[1]));
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[1]));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    assert_eq!(cm.peek_next_child().unwrap().v.len(), 0);
+    assert_eq!(cm.peek_child(1).unwrap().v.len(), 1);
+    assert!(cm.down());
+    cm.get_mut().v.push(x());
+    assert_eq!(cm.peek_next_child().unwrap().v.len(), 0);
+    assert!(cm.up());
+    assert_eq!(cm.peek_next_child().unwrap().v.len(), 1);
+    assert!(cm.down());
+    assert_eq!(cm.get().v.len(), 1);
+    drop(cm);
+    assert_eq!(mt.v[0].v.len(), 1);
+}
+
+#[test]
+fn rejoin() {
+    fn to_last_leaf(c: &mut TreeCursor<X>) -> usize {
+        let mut depth = 0;
+        while c.down_last() {
+            depth += 1;
+        }
+        assert!(!c.up_n(depth + 1));
+        depth
+    }
+
+    let t = xx(vec![
+        xx(vec![x(), xx(vec![x()])]),
+        x(),
+    ]);
+    let mut mt = t.clone();
+
+    let mut c = TreeCursor::new(&t);
+    assert!(c.take_cursor_rejoin(to_last_leaf).is_none());
+    assert_eq!(c.down_take_cursor_rejoin(to_last_leaf), Some(2));
+    assert!(ptr::eq(c.get(), &t.v[0].v[1].v[0]));
+    assert!(c.up());
+    assert!(c.up());
+    assert!(!c.down());
+    assert!(c.up());
+    assert!(c.down());
+    assert!(ptr::eq(c.get(), &t.v[1]));
+    assert!(c.up());
+    assert!(c.down_take_cursor_rejoin(to_last_leaf).is_none());
+    c.zero();
+    assert!(c.down());
+    assert_eq!(c.take_cursor_rejoin(|c| c.down()), Some(true));
+    assert!(ptr::eq(c.get(), &t.v[0].v[0]));
+
+    let mut cm = TreeCursorMut::new(&mut mt);
+    assert!(cm.down());
+    let r = cm.take_cursor_rejoin(|c| {
+        assert!(!c.up());
+        assert!(c.down_last());
+        c.get_mut().v.push(x());
+        c.get().v.len()
+    });
+    assert_eq!(r, Some(2));
+    cm.get_mut().v.clear();
+    assert!(cm.up());
+    assert!(cm.up());
+    assert!(cm.down());
+    assert!(cm.get().v.is_empty());
+    assert_eq!(cm.down_take_cursor_rejoin(|_| ()), None);
+    drop(cm);
+    assert_eq!(mt.v[0].v.len(), 2);
+    assert!(mt.v[0].v[1].v.is_empty());
+}
+
+#[test]
+fn relative_pos() {
+    let mut t = xx(vec![
+        x(),
+        xx(vec![x(), xx(vec![x()])]),
+    ]);
+
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    assert!(cm.up());
+    let base = cm.next_child_pos();
+    let rel = cm.down_take_cursor().map(|mut c| {
+        assert!(c.down());
+        assert!(c.up());
+        assert!(c.down());
+        assert!(c.down());
+        RelativePos::from(c.pos())
+    }).unwrap();
+    let abs = base.join(&rel);
+    assert_eq!(abs.relative_to(&base), Some(rel));
+
+    cm.set_pos(&abs);
+    assert!(cm.get().v.is_empty());
+    assert_eq!(cm.index_in_parent(), Some(0));
+    assert!(cm.up());
+    assert_eq!(cm.index_in_parent(), Some(1));
+    let other = cm.pos();
+    assert!(cm.up());
+    assert!(cm.up());
+    assert_eq!(cm.index_in_parent(), None);
+    assert_eq!(abs.relative_to(&cm.pos()).map(|r| cm.pos().join(&r)),
+               Some(abs.clone()));
+
+    // Positions from `take_cursor` are relative to the node that was active.
+    cm.set_pos(&other);
+    let base = cm.pos();
+    let rel = cm.take_cursor().map(|mut c| {
+        c.zero();
+        assert!(c.down());
+        RelativePos::from(c.pos())
+    }).unwrap();
+    assert_eq!(base.join(&rel), abs);
+
+    // Positions outside a subtree can't be made relative to it.
+    assert!(other.relative_to(&abs).is_none());
+    assert!(TreeCursorMut::new(&mut x()).pos().relative_to(&base).is_none());
+}
+
+#[test]
+fn pos_order() {
+    let mut t = xx(vec![
+        xx(vec![x(), x()]),
+        xx(vec![xx(vec![x()])]),
+    ]);
+    let mut cm = TreeCursorMut::new(&mut t);
+    let mut pos = |path: &[usize]| {
+        cm.to_root();
+        cm.zero();
+        for &i in path {
+            assert!(cm.down_nth(i));
+        }
+        cm.pos()
+    };
+
+    let root = pos(&[]);
+    let a = pos(&[0]);
+    let a0 = pos(&[0, 0]);
+    let a1 = pos(&[0, 1]);
+    let b = pos(&[1]);
+    let b00 = pos(&[1, 0, 0]);
+    assert_eq!(root.depth(), 0);
+    assert_eq!(b00.depth(), 3);
+
+    let mut v = vec![b00.clone(), a1.clone(), b.clone(), root.clone(),
+                     a0.clone(), a.clone()];
+    v.sort();
+    assert_eq!(v, [root.clone(), a.clone(), a0.clone(), a1.clone(),
+                   b.clone(), b00.clone()]);
+
+    // A node's own counter doesn't change which node a position refers to.
+    let a_visited = pos(&[0, 1]).parent().unwrap();
+    assert!(a < a_visited && a_visited < a0);
+    assert!(a_visited.is_ancestor_of(&a1));
+    assert!(!a.is_ancestor_of(&a_visited));
+    assert_eq!(a.depth(), a_visited.depth());
+
+    assert!(root.is_ancestor_of(&b00));
+    assert!(b.is_ancestor_of(&b00));
+    assert!(!a.is_ancestor_of(&b00));
+    assert!(!b00.is_ancestor_of(&b00));
+    assert!(b00.is_descendant_of(&root));
+    assert!(!b.is_descendant_of(&b00));
+    assert!(root.parent().is_none());
+
+    assert_eq!(TreeCursorPos::common_ancestor(&a0, &a1), a);
+    assert_eq!(TreeCursorPos::common_ancestor(&a1, &b00), root);
+    assert_eq!(TreeCursorPos::common_ancestor(&b, &b00), b);
+    assert_eq!(TreeCursorPos::common_ancestor(&b00, &b00), b00);
+
+    // A parent position leaves the parent's counter past the child.
+    cm.set_pos(&b00.parent().unwrap());
+    assert_eq!(cm.get().v.len(), 1);
+    assert!(!cm.down());
+}
+
+#[test]
+fn with_ancestor_mut() {
+    let mut t = xx(vec![
+        x(),
+        xx(vec![x(), xx(vec![x()])]),
+    ]);
+
+    {
+        let mut cm = TreeCursorMut::new(&mut t);
+        assert!(cm.down_nth(1));
+        assert!(cm.down());
+        assert!(cm.up());
+        assert!(cm.down());
+        assert!(cm.down());
+        let p = cm.pos();
+        assert_eq!(cm.with_ancestor_mut(4, |_| ()), None);
+        assert_eq!(cm.with_ancestor_mut(3, |n| {
+            n.v.push(x());
+            n.v.len()
+        }), Some(3));
+        assert_eq!(cm.with_ancestor_mut(0, |n| n.v.push(x())), Some(()));
+        assert_eq!(cm.pos(), p);
+        assert_eq!(cm.get().v.len(), 1);
+        assert_eq!(cm.with_ancestor_mut(1, |n| n.v.len()), Some(1));
+        assert!(cm.up());
+        assert!(!cm.down());
+        assert!(cm.up());
+        assert!(!cm.down());
+        assert!(cm.up());
+        assert!(cm.down());
+        assert!(cm.get().v.is_empty());
+    }
+    assert_eq!(t.v.len(), 3);
+    assert_eq!(t.v[1].v[1].v[0].v.len(), 1);
+
+    // Below a `down_map` move, there's no way back down.
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down_map(|n, _| n.v.get_mut(1)));
+    assert!(cm.down());
+    assert_eq!(cm.with_ancestor_mut(2, |n| n.v.clear()), None);
+    assert_eq!(cm.with_ancestor_mut(1, |n| n.v.len()), Some(2));
+    assert!(cm.up());
+    assert_eq!(cm.with_ancestor_mut(1, |n| n.v.clear()), None);
+    assert_eq!(cm.with_ancestor_mut(0, |n| n.v.len()), Some(2));
+    drop(cm);
+    assert_eq!(t.v.len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "missing node after with_ancestor_mut")]
+fn with_ancestor_mut_missing() {
+    let mut t = xx(vec![xx(vec![x()])]);
+    let mut cm = TreeCursorMut::new(&mut t);
+    assert!(cm.down());
+    assert!(cm.down());
+    cm.with_ancestor_mut(1, |n| n.v.clear());
+}
+
+#[test]
+#[should_panic(expected = "missing node in TreeCursorPos")]
+fn set_pos_zero_counter() {
+    let mut t = xx(vec![x()]);
+    let mut cm = TreeCursorMut::new(&mut t);
+    cm.set_pos(&TreeCursorPos(vec![0, 0]));
+}
@@ -0,0 +1,185 @@
+use cursor::TreeCursorMut;
+use prelude::*;
+use summary::{Summarize, SummaryCursor};
+
+struct N {
+    value: u32,
+    total: u32,
+    summarized: u32,
+    children: Vec<N>,
+}
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.children.get_mut(idx)
+    }
+}
+
+impl Summarize for N {
+    fn summarize(&mut self) {
+        self.total = self.value
+            + self.children.iter().map(|c| c.total).sum::<u32>();
+        self.summarized += 1;
+    }
+}
+
+fn n(value: u32, children: Vec<N>) -> N {
+    let mut n = N { value, total: 0, summarized: 0, children };
+    n.summarize();
+    n.summarized = 0;
+    n
+}
+
+fn t() -> N {
+    n(1, vec![
+        n(2, vec![n(3, vec![]), n(4, vec![n(5, vec![])])]),
+        n(6, vec![]),
+    ])
+}
+
+// Checks every cached total, and returns the number of times any node was
+// summarized.
+fn check(n: &N) -> u32 {
+    let sum: u32 = n.children.iter().map(|c| c.total).sum();
+    assert_eq!(n.total, n.value + sum);
+    n.summarized + n.children.iter().map(check).sum::<u32>()
+}
+
+#[test]
+fn up_recomputes() {
+    let mut t = t();
+    {
+        let mut c = SummaryCursor::new(TreeCursorMut::new(&mut t));
+        assert!(c.down());
+        assert!(c.down());
+        assert!(c.up());
+        assert!(c.down());
+        assert!(c.down());
+        assert_eq!(c.depth(), 3);
+        assert!(!c.is_dirty());
+        c.get_mut().value = 50;
+        assert!(c.is_dirty());
+        assert!(c.up());
+        assert!(c.is_dirty());
+        assert_eq!(c.get().total, 9);
+        assert!(c.up());
+        assert_eq!(c.get().total, 14);
+        assert!(c.up());
+        assert!(c.is_dirty());
+        assert_eq!(c.get().total, 21);
+        assert!(!c.up());
+        assert_eq!(c.get().total, 66);
+        assert!(!c.is_dirty());
+    }
+    // Only the changed node and its ancestors were summarized.
+    assert_eq!(check(&t), 4);
+    assert_eq!(t.total, 66);
+}
+
+#[test]
+fn update_on_demand() {
+    let mut t = t();
+    {
+        let mut c = SummaryCursor::new(TreeCursorMut::new(&mut t));
+        assert!(c.down());
+        c.get_mut().value = 20;
+        c.update();
+        assert!(!c.is_dirty());
+        assert_eq!(c.get().total, 32);
+        c.update();
+        assert!(c.down());
+        assert!(!c.is_dirty());
+        assert!(c.up());
+        assert!(c.up());
+        assert_eq!(c.get().total, 21);
+        assert!(!c.up());
+        assert_eq!(c.get().total, 39);
+    }
+    assert_eq!(check(&t), 2);
+}
+
+#[test]
+fn flush() {
+    let mut t = t();
+    let cm = {
+        let mut c = SummaryCursor::new(TreeCursorMut::new(&mut t));
+        assert!(c.down());
+        assert!(c.down());
+        c.get_mut().value = 30;
+        assert!(c.up());
+        assert!(c.down());
+        assert!(c.down());
+        c.get_mut().value = 0;
+        let p = c.pos();
+        assert!(c.flush());
+        assert_eq!(c.pos(), p);
+        assert!(!c.is_dirty());
+        assert!(c.up());
+        assert_eq!(c.get().total, 4);
+        assert!(c.flush());
+        assert!(!c.down());
+        c.zero();
+        assert!(c.down());
+        c.get_mut().value = 7;
+        c.into_inner()
+    };
+    assert_eq!(cm.get().value, 7);
+    drop(cm);
+    check(&t);
+    assert_eq!(t.total, 1 + 2 + 30 + 4 + 7 + 6);
+}
+
+#[test]
+fn taken_cursor() {
+    let mut t = t();
+    {
+        let mut cm = TreeCursorMut::new(&mut t);
+        assert!(cm.down());
+        let mut c = SummaryCursor::new(cm.down_take_cursor().unwrap());
+        assert_eq!(c.depth(), 0);
+        c.get_mut().value = 13;
+        assert!(c.flush());
+    }
+    // The taken cursor's ancestors weren't summarized.
+    assert_eq!(t.children[0].children[0].total, 13);
+    assert_eq!(t.children[0].total, 14);
+    assert_eq!(t.total, 21);
+}
+
+#[test]
+fn flush_after_down_map() {
+    let mut t = n(1, vec![n(2, vec![]), n(3, vec![])]);
+    {
+        let mut c = SummaryCursor::new(TreeCursorMut::new(&mut t));
+        assert!(c.down_map(|n, idx| n.children.get_mut(1 - idx)));
+        c.get_mut().value = 10;
+        // `flush` can't find its way back, so it leaves everything alone.
+        assert!(!c.flush());
+        assert_eq!(c.get().value, 10);
+        assert_eq!(c.depth(), 1);
+        assert!(c.is_dirty());
+        assert!(c.up());
+        assert_eq!(c.get().total, 6);
+        assert!(c.flush());
+        assert_eq!(c.get().total, 13);
+
+        // Moving up past the node first keeps the cursor where it is.
+        c.zero();
+        assert!(c.down_map(|n, idx| n.children.get_mut(1 - idx)));
+        c.get_mut().value = 20;
+        assert!(c.up());
+        assert!(c.flush());
+        assert_eq!(c.get().total, 23);
+    }
+    assert_eq!(check(&t), 4);
+}
+
+#[test]
+#[should_panic(expected = "can't flush summaries after down_map")]
+fn into_inner_after_down_map() {
+    let mut t = n(1, vec![n(2, vec![]), n(3, vec![])]);
+    let mut c = SummaryCursor::new(TreeCursorMut::new(&mut t));
+    assert!(c.down_map(|n, idx| n.children.get_mut(1 - idx)));
+    c.get_mut().value = 10;
+    c.into_inner();
+}
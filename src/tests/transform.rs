@@ -0,0 +1,209 @@
+use cursor::{TreeCursorMut, TreeCursorPos};
+use prelude::*;
+use transform::StructuralEdit;
+
+#[derive(Clone, Debug)]
+struct N(u32, Vec<N>);
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.1.get_mut(idx)
+    }
+}
+
+impl ExactSizeDown for N {
+    fn child_count(&self) -> usize {
+        self.1.len()
+    }
+}
+
+fn t() -> N {
+    N(0, vec![
+        N(1, vec![N(2, vec![]), N(3, vec![N(4, vec![])])]),
+        N(5, vec![]),
+        N(6, vec![N(7, vec![]), N(8, vec![])]),
+    ])
+}
+
+fn pos(t: &mut N, path: &[usize]) -> TreeCursorPos {
+    let mut c = TreeCursorMut::new(t);
+    for &i in path {
+        assert!(c.down_nth(i));
+    }
+    c.pos()
+}
+
+fn label(t: &mut N, p: &TreeCursorPos) -> u32 {
+    let mut c = TreeCursorMut::new(t);
+    c.set_pos(p);
+    c.get().0
+}
+
+fn paths(n: &N, prefix: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+    out.push(prefix.clone());
+    for (i, child) in n.1.iter().enumerate() {
+        prefix.push(i);
+        paths(child, prefix, out);
+        prefix.pop();
+    }
+}
+
+fn node_mut<'n>(t: &'n mut N, path: &[usize]) -> &'n mut N {
+    path.iter().fold(t, |n, &i| &mut n.1[i])
+}
+
+fn insert(t: &mut N, path: &[usize], n: N) {
+    let (&i, parent) = path.split_last().unwrap();
+    node_mut(t, parent).1.insert(i, n);
+}
+
+fn remove(t: &mut N, path: &[usize]) -> N {
+    let (&i, parent) = path.split_last().unwrap();
+    node_mut(t, parent).1.remove(i)
+}
+
+// Saves the position of every node in `t`, lets `f` change the tree and
+// return the matching edit, and checks that each transformed position refers
+// to the node it did before, unless that node was deleted.
+fn check<F>(f: F) -> Vec<u32>
+where
+    F: FnOnce(&mut N) -> StructuralEdit,
+{
+    let mut t = t();
+    let mut all = Vec::new();
+    paths(&t, &mut vec![], &mut all);
+    let before: Vec<_> = all.iter().map(|p| {
+        let p = pos(&mut t, p);
+        (label(&mut t, &p), p)
+    }).collect();
+    let edit = f(&mut t);
+    let mut deleted = Vec::new();
+    for (l, p) in before {
+        match edit.transform(&p) {
+            Some(q) => assert_eq!(label(&mut t, &q), l),
+            None => deleted.push(l),
+        }
+    }
+    deleted
+}
+
+#[test]
+fn insert_and_delete() {
+    assert!(check(|t| {
+        insert(t, &[0], N(9, vec![]));
+        StructuralEdit::Insert(pos(t, &[0]))
+    }).is_empty());
+    assert!(check(|t| {
+        insert(t, &[0, 1], N(9, vec![N(10, vec![])]));
+        StructuralEdit::Insert(pos(t, &[0, 1]))
+    }).is_empty());
+    assert!(check(|t| {
+        insert(t, &[3], N(9, vec![]));
+        StructuralEdit::Insert(pos(t, &[3]))
+    }).is_empty());
+
+    assert_eq!(check(|t| {
+        let edit = StructuralEdit::Delete(pos(t, &[0]));
+        remove(t, &[0]);
+        edit
+    }), [1, 2, 3, 4]);
+    assert_eq!(check(|t| {
+        let edit = StructuralEdit::Delete(pos(t, &[2, 0]));
+        remove(t, &[2, 0]);
+        edit
+    }), [7]);
+    assert_eq!(check(|t| {
+        let edit = StructuralEdit::Delete(pos(t, &[0, 1, 0]));
+        remove(t, &[0, 1, 0]);
+        edit
+    }), [4]);
+    assert_eq!(check(|_| StructuralEdit::Delete(pos(&mut t(), &[]))).len(),
+               9);
+}
+
+#[test]
+fn moves() {
+    let cases: [(&[usize], &[usize]); 7] = [
+        (&[0], &[2]),
+        (&[2], &[0]),
+        (&[0, 1], &[1, 0]),
+        (&[2, 1], &[0, 0, 0]),
+        (&[0, 1, 0], &[1]),
+        (&[1], &[1, 2]),
+        (&[1], &[1]),
+    ];
+    for &(from, to) in &cases {
+        assert!(check(|t| {
+            let from_pos = pos(t, from);
+            let n = remove(t, from);
+            insert(t, to, n);
+            StructuralEdit::Move { from: from_pos, to: pos(t, to) }
+        }).is_empty(), "{:?} -> {:?}", from, to);
+    }
+}
+
+#[test]
+fn counters() {
+    let mut t = t();
+    // The root's counter is past its first two children.
+    let p = {
+        let mut c = TreeCursorMut::new(&mut t);
+        assert!(c.down());
+        assert!(c.up());
+        assert!(c.down());
+        assert!(c.up());
+        c.pos()
+    };
+
+    // Inserting a node before the second child leaves the counter past it.
+    insert(&mut t, &[1], N(9, vec![]));
+    let edit = StructuralEdit::Insert(pos(&mut t, &[1]));
+    let p = edit.transform(&p).unwrap();
+    {
+        let mut c = TreeCursorMut::new(&mut t);
+        c.set_pos(&p);
+        assert!(c.down());
+        assert_eq!(c.get().0, 6);
+    }
+
+    let edit = StructuralEdit::Delete(pos(&mut t, &[0]));
+    remove(&mut t, &[0]);
+    let p = edit.transform(&p).unwrap();
+    {
+        let mut c = TreeCursorMut::new(&mut t);
+        c.set_pos(&p);
+        assert!(c.down());
+        assert_eq!(c.get().0, 6);
+    }
+
+    // Inserting a node at exactly the index a counter points to leaves the
+    // counter pointing at the child it did before.
+    let p = pos(&mut t, &[]);
+    insert(&mut t, &[0], N(10, vec![]));
+    let p = StructuralEdit::Insert(pos(&mut t, &[0])).transform(&p).unwrap();
+    let p = {
+        let mut c = TreeCursorMut::new(&mut t);
+        c.set_pos(&p);
+        assert!(c.down());
+        assert_eq!(c.get().0, 9);
+        assert!(c.up());
+        assert!(c.down_nth(3));
+        assert!(c.down());
+        assert!(c.up());
+        c.pos()
+    };
+    insert(&mut t, &[3, 1], N(11, vec![]));
+    let p = StructuralEdit::Insert(pos(&mut t, &[3, 1])).transform(&p);
+    let mut c = TreeCursorMut::new(&mut t);
+    c.set_pos(&p.unwrap());
+    assert!(c.down());
+    assert_eq!(c.get().0, 8);
+}
+
+#[test]
+#[should_panic(expected = "can't insert a node at the root")]
+fn insert_root() {
+    let mut t = t();
+    let p = pos(&mut t, &[0]);
+    StructuralEdit::Insert(pos(&mut t, &[])).transform(&p);
+}
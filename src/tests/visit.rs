@@ -0,0 +1,154 @@
+use cursor::{TreeCursor, TreeCursorMut};
+use prelude::*;
+use std::ops::ControlFlow;
+use visit::{self, VisitAction, Visitor, VisitorMut};
+
+#[derive(Clone)]
+struct N(&'static str, Vec<N>);
+
+impl Down for N {
+    fn down(&self, idx: usize) -> Option<&Self> {
+        self.1.get(idx)
+    }
+}
+
+impl DownMut for N {
+    fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+        self.1.get_mut(idx)
+    }
+}
+
+fn t() -> N {
+    N("a", vec![
+        N("b", vec![
+            N("c", vec![]),
+            N("d", vec![]),
+        ]),
+        N("e", vec![]),
+    ])
+}
+
+// Records every hook call, and acts on the ones it's told to.
+struct Log {
+    log: Vec<String>,
+    skip: &'static str,
+    stop_enter: &'static str,
+    stop_leave: &'static str,
+}
+
+impl Log {
+    fn new() -> Self {
+        Log { log: vec![], skip: "", stop_enter: "", stop_leave: "" }
+    }
+}
+
+impl Visitor<N> for Log {
+    type Break = &'static str;
+
+    fn enter(&mut self, node: &N) -> VisitAction<&'static str> {
+        self.log.push(format!("+{}", node.0));
+        if node.0 == self.skip {
+            VisitAction::SkipChildren
+        } else if node.0 == self.stop_enter {
+            VisitAction::Stop(node.0)
+        } else {
+            VisitAction::Continue
+        }
+    }
+
+    fn leave(&mut self, node: &N) -> ControlFlow<&'static str> {
+        self.log.push(format!("-{}", node.0));
+        if node.0 == self.stop_leave {
+            ControlFlow::Break(node.0)
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+#[test]
+fn full() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let mut v = Log::new();
+    assert_eq!(visit::walk(&mut c, &mut v), ControlFlow::Continue(()));
+    assert_eq!(v.log, [
+        "+a", "+b", "+c", "-c", "+d", "-d", "-b", "+e", "-e", "-a",
+    ]);
+    assert_eq!(c.get().0, "a");
+    assert!(!c.up());
+}
+
+#[test]
+fn skip_children() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let mut v = Log { skip: "b", ..Log::new() };
+    assert_eq!(visit::walk(&mut c, &mut v), ControlFlow::Continue(()));
+    assert_eq!(v.log, ["+a", "+b", "-b", "+e", "-e", "-a"]);
+}
+
+#[test]
+fn stop() {
+    let t = t();
+    let mut c = TreeCursor::new(&t);
+    let mut v = Log { stop_enter: "d", ..Log::new() };
+    assert_eq!(visit::walk(&mut c, &mut v), ControlFlow::Break("d"));
+    assert_eq!(v.log, ["+a", "+b", "+c", "-c", "+d"]);
+    assert_eq!(c.get().0, "d");
+
+    // Walking again from the parent covers all of its children, even though
+    // its counter has moved past some of them.
+    assert!(c.up());
+    let mut v = Log::new();
+    assert_eq!(visit::walk(&mut c, &mut v), ControlFlow::Continue(()));
+    assert_eq!(v.log, ["+b", "+c", "-c", "+d", "-d", "-b"]);
+
+    let mut c = TreeCursor::new(&t);
+    let mut v = Log { stop_leave: "b", ..Log::new() };
+    assert_eq!(visit::walk(&mut c, &mut v), ControlFlow::Break("b"));
+    assert_eq!(v.log, ["+a", "+b", "+c", "-c", "+d", "-d", "-b"]);
+    assert_eq!(c.get().0, "b");
+}
+
+struct Rename;
+
+impl VisitorMut<N> for Rename {
+    type Break = ();
+
+    fn enter(&mut self, node: &mut N) -> VisitAction {
+        match node.0 {
+            "b" => {
+                node.1.push(N("x", vec![]));
+                VisitAction::Continue
+            },
+            "x" => VisitAction::Stop(()),
+            _ => {
+                node.0 = "z";
+                VisitAction::Continue
+            },
+        }
+    }
+
+    fn leave(&mut self, node: &mut N) -> ControlFlow<()> {
+        node.0 = if node.0 == "z" { "y" } else { node.0 };
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn walk_mut() {
+    let mut t = t();
+    {
+        let mut cm = TreeCursorMut::new(&mut t);
+        let r = visit::walk_mut(&mut cm, &mut Rename);
+        assert_eq!(r, ControlFlow::Break(()));
+        assert_eq!(cm.get().0, "x");
+        assert!(cm.up());
+        assert_eq!(cm.get().0, "b");
+    }
+    assert_eq!(t.0, "z");
+    let names: Vec<_> = t.1[0].1.iter().map(|n| n.0).collect();
+    assert_eq!(names, ["y", "y", "x"]);
+    assert_eq!(t.1[1].0, "e");
+}
@@ -0,0 +1,178 @@
+//! Keeping saved positions pointed at the same nodes across structural
+//! edits.
+//!
+//! A [`TreeCursorPos`] stores child indices, so inserting, deleting, or
+//! moving a node changes which node the positions of its later siblings (and
+//! their descendants) refer to. Describe each such edit with a
+//! [`StructuralEdit`] and pass every saved position through its
+//! [`transform`] method to get a position that refers to the same node as
+//! before, or `None` if that node was deleted. Edits must be transformed
+//! against in the order they were made.
+//!
+//! ```
+//! use tree_cursor::cursor::TreeCursorMut;
+//! use tree_cursor::prelude::*;
+//! use tree_cursor::transform::StructuralEdit;
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl DownMut for Node {
+//!     fn down_mut(&mut self, idx: usize) -> Option<&mut Self> {
+//!         self.1.get_mut(idx)
+//!     }
+//! }
+//!
+//! let mut t = Node("a", vec![Node("b", vec![]), Node("c", vec![])]);
+//! let mut cur = TreeCursorMut::new(&mut t);
+//! assert!(cur.down());
+//! let b = cur.pos();
+//! assert!(cur.up());
+//! assert!(cur.down());
+//! let c = cur.pos();
+//!
+//! // Insert a node before "b".
+//! assert!(cur.up());
+//! cur.get_mut().1.insert(0, Node("x", vec![]));
+//! let edit = StructuralEdit::Insert(b.clone());
+//! let c = edit.transform(&c).unwrap();
+//! cur.set_pos(&c);
+//! assert_eq!(cur.get().0, "c");
+//!
+//! // Delete "b".
+//! let b = edit.transform(&b).unwrap();
+//! assert!(cur.up());
+//! cur.get_mut().1.remove(1);
+//! let edit = StructuralEdit::Delete(b.clone());
+//! assert_eq!(edit.transform(&b), None);
+//! cur.set_pos(&edit.transform(&c).unwrap());
+//! assert_eq!(cur.get().0, "c");
+//! ```
+//!
+//! [`TreeCursorPos`]: ::cursor::TreeCursorPos
+//! [`transform`]: StructuralEdit::transform
+
+use cursor::TreeCursorPos;
+
+/// A change to the shape of a tree. See the [module documentation](self).
+///
+/// Only the paths of the positions in an edit matter; their nodes' own "next
+/// child" counters are ignored.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum StructuralEdit {
+    /// A node was inserted at this position, after the edit, shifting its
+    /// following siblings along by one. Its children don't matter, since no
+    /// saved position can refer to them.
+    Insert(TreeCursorPos),
+    /// The node at this position, before the edit, was removed along with
+    /// its subtree, shifting its following siblings back by one.
+    Delete(TreeCursorPos),
+    /// The node at `from`, before the edit, was moved along with its subtree
+    /// so that it's at `to` after the edit. This is the same as deleting it
+    /// and then inserting it at `to`, except that positions in the moved
+    /// subtree move with it.
+    Move {
+        /// The position of the moved node before the edit.
+        from: TreeCursorPos,
+        /// The position of the moved node after the edit. It can't be in
+        /// the moved subtree.
+        to: TreeCursorPos,
+    },
+}
+
+impl StructuralEdit {
+    /// Returns the position that refers to the same node after this edit as
+    /// `pos` did before it, or `None` if that node was deleted. The "next
+    /// child" counters of the nodes on the path, including the node at `pos`
+    /// itself, are adjusted along with it, so that [`down`] still moves to
+    /// the same child.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if a node is inserted or moved at the root, since
+    /// a tree can only have one.
+    ///
+    /// [`down`]: ::cursor::TreeCursorMut::down
+    pub fn transform(&self, pos: &TreeCursorPos) -> Option<TreeCursorPos> {
+        let mut pos = pos.clone();
+        match *self {
+            StructuralEdit::Insert(ref at) => insert(&mut pos, at),
+            StructuralEdit::Delete(ref at) => {
+                if !delete(&mut pos, at) {
+                    return None;
+                }
+            },
+            StructuralEdit::Move { ref from, ref to } => {
+                let prefix = from.path();
+                if pos.path().starts_with(prefix) {
+                    let mut v = to.path().to_vec();
+                    assert!(!v.is_empty(), "can't move a node to the root");
+                    v.extend_from_slice(&pos.0[prefix.len()..]);
+                    return Some(TreeCursorPos(v));
+                }
+                // `pos` isn't in the moved subtree, so it wasn't deleted.
+                delete(&mut pos, from);
+                insert(&mut pos, to);
+            },
+        }
+        Some(pos)
+    }
+}
+
+// Returns the depth of the parent of the node at `at`, and that node's "next
+// child" counter for the node at `at`, or `None` if it's the root.
+fn parent_counter(at: &TreeCursorPos) -> Option<(usize, usize)> {
+    let depth = at.depth().checked_sub(1)?;
+    Some((depth, at.0[depth]))
+}
+
+// Returns the counter in `pos` of the parent of the node at `at`, if `pos` is
+// at or below that parent. On the path, that counter is one more than the
+// index of a child; as the node's own counter, it's the index of the next
+// child `down` moves to.
+fn sibling_counter<'p>(
+    pos: &'p mut TreeCursorPos,
+    at: &TreeCursorPos,
+    depth: usize,
+) -> Option<&'p mut usize> {
+    if pos.0.len() > depth && pos.0[..depth] == at.0[..depth] {
+        Some(&mut pos.0[depth])
+    } else {
+        None
+    }
+}
+
+fn insert(pos: &mut TreeCursorPos, at: &TreeCursorPos) {
+    let (depth, at_counter) = parent_counter(at)
+        .expect("can't insert a node at the root");
+    // The inserted node's index is `at_counter - 1`. A path counter needs to
+    // move if it's one past that index or more, and the node's own counter
+    // if it's that index or more.
+    let threshold = if depth == pos.depth() {
+        at_counter - 1
+    } else {
+        at_counter
+    };
+    if let Some(c) = sibling_counter(pos, at, depth) {
+        if *c >= threshold {
+            *c += 1;
+        }
+    }
+}
+
+// Returns false if the node at `pos` was deleted.
+fn delete(pos: &mut TreeCursorPos, at: &TreeCursorPos) -> bool {
+    if pos.path().starts_with(at.path()) {
+        return false;
+    }
+    // Deleting the root deletes everything, so `at` isn't the root.
+    let (depth, at_counter) = parent_counter(at).unwrap();
+    // Either kind of counter needs to move if it's past the deleted node's
+    // index, `at_counter - 1`. A path counter can't be one past it, since
+    // `pos` would then have been deleted.
+    if let Some(c) = sibling_counter(pos, at, depth) {
+        if *c >= at_counter {
+            *c -= 1;
+        }
+    }
+    true
+}
@@ -0,0 +1,180 @@
+//! Structured depth-first traversal with enter/leave hooks.
+//!
+//! A [`Visitor`] is called when the traversal enters and leaves each node,
+//! and decides whether to visit the node's children or to stop early. [`walk`]
+//! and [`walk_mut`] drive a cursor through the subtree rooted at its active
+//! node and report whether the traversal ran to completion.
+//!
+//! ```
+//! use std::ops::ControlFlow;
+//! use tree_cursor::cursor::TreeCursor;
+//! use tree_cursor::prelude::*;
+//! use tree_cursor::visit::{self, VisitAction, Visitor};
+//!
+//! struct Node(&'static str, Vec<Node>);
+//!
+//! impl Down for Node {
+//!     fn down(&self, idx: usize) -> Option<&Self> {
+//!         self.1.get(idx)
+//!     }
+//! }
+//!
+//! struct Find(&'static str, usize);
+//!
+//! impl Visitor<Node> for Find {
+//!     type Break = usize;
+//!
+//!     fn enter(&mut self, node: &Node) -> VisitAction<usize> {
+//!         self.1 += 1;
+//!         if node.0 == self.0 {
+//!             VisitAction::Stop(self.1)
+//!         } else {
+//!             VisitAction::Continue
+//!         }
+//!     }
+//! }
+//!
+//! let t = Node("foo", vec![
+//!     Node("bar", vec![Node("baz", vec![])]),
+//!     Node("zup", vec![]),
+//! ]);
+//! let mut cur = TreeCursor::new(&t);
+//! let found = visit::walk(&mut cur, &mut Find("zup", 0));
+//! assert_eq!(found, ControlFlow::Break(4));
+//! assert_eq!(cur.get().0, "zup");
+//! ```
+
+use core::ops::ControlFlow;
+use cursor::{TreeCursor, TreeCursorMut};
+use observe::Observer;
+use prelude::*;
+
+/// What a traversal should do after entering a node.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VisitAction<B = ()> {
+    /// Visit the node's children, then leave it.
+    Continue,
+    /// Leave the node without visiting its children.
+    SkipChildren,
+    /// End the traversal immediately, without leaving the node or any of its
+    /// ancestors. The value is returned from the walk function.
+    Stop(B),
+}
+
+/// Callbacks for [`walk`]. See the [module documentation](self).
+pub trait Visitor<N> {
+    /// The value that ends a traversal early.
+    type Break;
+
+    /// Called when the traversal enters `node`.
+    fn enter(&mut self, node: &N) -> VisitAction<Self::Break>;
+
+    /// Called when the traversal leaves `node`, after its children (unless
+    /// they were skipped). Returning `ControlFlow::Break` ends the traversal
+    /// without leaving any of `node`'s ancestors.
+    fn leave(&mut self, _node: &N) -> ControlFlow<Self::Break> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Like [`Visitor`], but with mutable access to each node, for
+/// [`walk_mut`].
+pub trait VisitorMut<N> {
+    /// See [`Visitor::Break`].
+    type Break;
+
+    /// See [`Visitor::enter`]. If this changes the node's children, the
+    /// traversal visits the new ones.
+    fn enter(&mut self, node: &mut N) -> VisitAction<Self::Break>;
+
+    /// See [`Visitor::leave`].
+    fn leave(&mut self, _node: &mut N) -> ControlFlow<Self::Break> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Visits the subtree rooted at `cur`'s active node in depth-first order,
+/// calling `v`'s hooks on each node.
+///
+/// The active node's "next child" counter is reset first, so the traversal
+/// always covers the whole subtree, as with [`Events`](::events::Events).
+/// After that, the cursor is driven with [`down`] and [`up`], so the usual
+/// effects on "next child" counters apply. If the traversal runs to
+/// completion, this returns `ControlFlow::Continue` and the cursor is back at
+/// the node it started at. If `v` stops it, this returns `v`'s value and the
+/// cursor is left at the node whose hook stopped it.
+///
+/// [`down`]: TreeCursor::down
+/// [`up`]: TreeCursor::up
+pub fn walk<'n, 'f, N, V, O>(
+    cur: &mut TreeCursor<'n, 'f, N, O>,
+    v: &mut V,
+) -> ControlFlow<V::Break>
+where
+    N: 'n + Down,
+    V: Visitor<N> + ?Sized,
+    O: Observer,
+{
+    cur.zero();
+    let mut depth = 0;
+    loop {
+        let descend = match v.enter(cur.get()) {
+            VisitAction::Continue => true,
+            VisitAction::SkipChildren => false,
+            VisitAction::Stop(b) => return ControlFlow::Break(b),
+        };
+        if descend && cur.down() {
+            depth += 1;
+            continue;
+        }
+        loop {
+            v.leave(cur.get())?;
+            if depth == 0 {
+                return ControlFlow::Continue(());
+            }
+            assert!(cur.up());
+            depth -= 1;
+            if cur.down() {
+                depth += 1;
+                break;
+            }
+        }
+    }
+}
+
+/// Like [`walk`], but for a [`TreeCursorMut`] and a [`VisitorMut`].
+pub fn walk_mut<'n, 'f, N, V, O>(
+    cur: &mut TreeCursorMut<'n, 'f, N, O>,
+    v: &mut V,
+) -> ControlFlow<V::Break>
+where
+    N: 'n + DownMut,
+    V: VisitorMut<N> + ?Sized,
+    O: Observer,
+{
+    cur.zero();
+    let mut depth = 0;
+    loop {
+        let descend = match v.enter(cur.get_mut()) {
+            VisitAction::Continue => true,
+            VisitAction::SkipChildren => false,
+            VisitAction::Stop(b) => return ControlFlow::Break(b),
+        };
+        if descend && cur.down() {
+            depth += 1;
+            continue;
+        }
+        loop {
+            v.leave(cur.get_mut())?;
+            if depth == 0 {
+                return ControlFlow::Continue(());
+            }
+            assert!(cur.up());
+            depth -= 1;
+            if cur.down() {
+                depth += 1;
+                break;
+            }
+        }
+    }
+}